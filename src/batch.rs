@@ -0,0 +1,97 @@
+use macroquad::models::{Mesh, Vertex, draw_mesh};
+use macroquad::prelude::*;
+
+// a macroquad draw call holds 5000 indices by default and every line takes 6 of them
+const MAX_LINES_PER_DRAW: usize = 800;
+
+/// Collects lines in screen coordinates and draws them as one mesh,
+/// instead of issuing a draw call for every line.
+pub struct LineBatch {
+    mesh: Mesh,
+    line_count: usize,
+}
+
+impl Default for LineBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineBatch {
+    pub fn new() -> Self {
+        Self {
+            mesh: Mesh {
+                vertices: vec![],
+                indices: vec![],
+                texture: None,
+            },
+            line_count: 0,
+        }
+    }
+
+    pub fn add_line(&mut self, start: Vec2, end: Vec2, thickness: f32, color: Color) {
+        let Some(direction) = (end - start).try_normalize() else {
+            return;
+        };
+        if self.line_count == MAX_LINES_PER_DRAW {
+            self.flush();
+        }
+
+        // every line is a thin quad made of two triangles
+        let offset = direction.perp() * thickness * 0.5;
+        let first = self.mesh.vertices.len() as u16;
+        for corner in [start + offset, end + offset, end - offset, start - offset] {
+            self.mesh
+                .vertices
+                .push(Vertex::new(corner.x, corner.y, 0.0, 0.0, 0.0, color));
+        }
+        self.mesh.indices.extend_from_slice(&[
+            first,
+            first + 1,
+            first + 2,
+            first,
+            first + 2,
+            first + 3,
+        ]);
+        self.line_count += 1;
+    }
+
+    /// the outline of a circle, approximated by a polygon with `sides` sides
+    pub fn add_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        sides: usize,
+        thickness: f32,
+        color: Color,
+    ) {
+        let corner = |i: usize| {
+            let angle = i as f32 / sides as f32 * std::f32::consts::TAU;
+            center + Vec2::from_angle(angle) * radius
+        };
+        for i in 0..sides {
+            self.add_line(corner(i), corner(i + 1), thickness, color);
+        }
+    }
+
+    pub fn add_rectangle(&mut self, top_left: Vec2, size: Vec2, thickness: f32, color: Color) {
+        let top_right = top_left + vec2(size.x, 0.0);
+        let bottom_right = top_left + size;
+        let bottom_left = top_left + vec2(0.0, size.y);
+        self.add_line(top_left, top_right, thickness, color);
+        self.add_line(top_right, bottom_right, thickness, color);
+        self.add_line(bottom_right, bottom_left, thickness, color);
+        self.add_line(bottom_left, top_left, thickness, color);
+    }
+
+    /// Draws everything collected so far and empties the batch.
+    pub fn flush(&mut self) {
+        if self.line_count == 0 {
+            return;
+        }
+        draw_mesh(&self.mesh);
+        self.mesh.vertices.clear();
+        self.mesh.indices.clear();
+        self.line_count = 0;
+    }
+}
@@ -1,9 +1,29 @@
 use ::macroquad::prelude::*;
 
+/// The direction the y axis of the world points to on the screen.
+/// The screen itself is always Y-down. With `YUp` the world's `UP` is drawn upwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateSystem {
+    YUp,
+    YDown,
+}
+
+impl CoordinateSystem {
+    /// the factor that world coordinates are multiplied with to get screen coordinates
+    pub fn axis_signs(&self) -> Vec2 {
+        match self {
+            CoordinateSystem::YUp => vec2(1.0, -1.0),
+            CoordinateSystem::YDown => vec2(1.0, 1.0),
+        }
+    }
+}
+
 pub struct Camera {
     pub screen_dims: Vec2,
     pub pos: Vec2,
+    // pixels per world unit, the y flip is handled by `coordinate_system`
     pub zoom: Vec2,
+    pub coordinate_system: CoordinateSystem,
     // the factor to multiply with/divide by when performing a zoom/unzoom operation
     pub zoom_factor: f32,
 }
@@ -19,6 +39,32 @@ impl Camera {
         self.zoom.y /= self.zoom_factor;
     }
 
+    /// Moves and zooms the camera so that the region between `min` and `max` fills the screen,
+    /// leaving `padding` pixels on every side. The zoom stays uniform, so the axis that
+    /// needs the smaller zoom decides it.
+    pub fn fit(&mut self, min: Vec2, max: Vec2, padding: f32) {
+        self.pos = (min + max) * 0.5;
+
+        let size = (max - min).abs();
+        let available = (self.screen_dims - Vec2::splat(2.0 * padding)).max(Vec2::ONE);
+
+        // an axis without extent doesn't limit the zoom
+        let zoom_x = if size.x > 0.0 {
+            available.x / size.x
+        } else {
+            f32::INFINITY
+        };
+        let zoom_y = if size.y > 0.0 {
+            available.y / size.y
+        } else {
+            f32::INFINITY
+        };
+        let zoom = f32::min(zoom_x, zoom_y);
+        if zoom.is_finite() {
+            self.zoom = Vec2::splat(zoom);
+        }
+    }
+
     pub fn screen_middle(&self) -> Vec2 {
         Vec2 {
             x: self.screen_dims.x / 2.0,
@@ -35,11 +81,30 @@ impl Camera {
 
         Formula: screen_pos = (obj_pos - cam_pos) * zoom + screen_center
         */
-        let relative_position: Vec2 = (world_pos - self.pos) * self.zoom;
+        let relative_position: Vec2 = self.world_to_screen_vec(world_pos - self.pos);
 
         self.screen_middle() + relative_position
     }
 
+    /// Converts a direction or size from world units to screen pixels. Unlike `world_to_screen`
+    /// this doesn't depend on the camera position.
+    pub fn world_to_screen_vec(&self, world_vec: Vec2) -> Vec2 {
+        world_vec * self.zoom * self.coordinate_system.axis_signs()
+    }
+
+    /// Converts a direction or size from screen pixels to world units.
+    pub fn screen_to_world_vec(&self, screen_vec: Vec2) -> Vec2 {
+        screen_vec / (self.zoom * self.coordinate_system.axis_signs())
+    }
+
+    /// Converts a counter-clockwise rotation in the world to the rotation to draw it with.
+    /// Flipping one axis mirrors the screen, so with `YUp` a positive world angle turns the
+    /// other way on the screen.
+    pub fn world_angle_to_screen(&self, angle: f32) -> f32 {
+        let signs = self.coordinate_system.axis_signs();
+        angle * signs.x * signs.y
+    }
+
     /// Converts the position on the screen to the position in the world using the camera parameters
     pub fn screen_to_world(&self, screen_pos: Vec2) -> Vec2 {
         /*
@@ -48,7 +113,7 @@ impl Camera {
 
         Formula: world_pos = (screen_pos - screen_center) / zoom + cam_pos
         */
-        let relative_position: Vec2 = (screen_pos - self.screen_middle()) / self.zoom;
+        let relative_position: Vec2 = self.screen_to_world_vec(screen_pos - self.screen_middle());
 
         self.pos + relative_position
     }
@@ -58,9 +123,81 @@ impl Default for Camera {
     fn default() -> Self {
         Self {
             pos: Vec2::ZERO,
-            zoom: vec2(24.0, -24.0),
+            zoom: vec2(24.0, 24.0),
+            coordinate_system: CoordinateSystem::YUp,
             zoom_factor: 1.1,
             screen_dims: vec2(screen_width(), screen_height()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use macroquad::rand::{gen_range, srand};
+
+    // Camera::default needs a window for the screen size
+    fn camera(pos: Vec2, zoom: Vec2, coordinate_system: CoordinateSystem) -> Camera {
+        Camera {
+            screen_dims: vec2(800.0, 600.0),
+            pos,
+            zoom,
+            coordinate_system,
+            zoom_factor: 1.1,
+        }
+    }
+
+    fn configurations() -> Vec<Camera> {
+        let mut cameras = vec![];
+        for coordinate_system in [CoordinateSystem::YUp, CoordinateSystem::YDown] {
+            cameras.push(camera(Vec2::ZERO, vec2(24.0, 24.0), coordinate_system));
+            cameras.push(camera(
+                vec2(-35.0, 120.5),
+                vec2(0.25, 0.25),
+                coordinate_system,
+            ));
+            cameras.push(camera(
+                vec2(1000.0, -3.0),
+                vec2(80.0, 12.0),
+                coordinate_system,
+            ));
+        }
+        cameras
+    }
+
+    #[test]
+    fn world_to_screen_and_back_round_trips() {
+        srand(42);
+        for camera in configurations() {
+            for _ in 0..100 {
+                let world_pos = vec2(gen_range(-500.0, 500.0), gen_range(-500.0, 500.0));
+                let round_trip = camera.screen_to_world(camera.world_to_screen(world_pos));
+                assert_relative_eq!(round_trip.x, world_pos.x, epsilon = 1e-3);
+                assert_relative_eq!(round_trip.y, world_pos.y, epsilon = 1e-3);
+
+                // far from the origin the world position only has a few bits left for the
+                // fraction, which the zoom then magnifies. A hundredth of a pixel is still exact
+                let screen_pos = vec2(gen_range(0.0, 800.0), gen_range(0.0, 600.0));
+                let round_trip = camera.world_to_screen(camera.screen_to_world(screen_pos));
+                assert_relative_eq!(round_trip.x, screen_pos.x, epsilon = 1e-2);
+                assert_relative_eq!(round_trip.y, screen_pos.y, epsilon = 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn camera_position_is_drawn_in_the_middle_and_up_follows_the_coordinate_system() {
+        for camera in configurations() {
+            let middle = camera.world_to_screen(camera.pos);
+            assert_relative_eq!(middle.x, camera.screen_middle().x);
+            assert_relative_eq!(middle.y, camera.screen_middle().y);
+
+            let above = camera.world_to_screen(camera.pos + crate::UP);
+            match camera.coordinate_system {
+                CoordinateSystem::YUp => assert!(above.y < middle.y),
+                CoordinateSystem::YDown => assert!(above.y > middle.y),
+            }
+        }
+    }
+}
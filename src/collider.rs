@@ -3,13 +3,27 @@ use crate::rigid_body::*;
 use macroquad::prelude::*;
 
 #[derive(Clone)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Collider {
     Circle { offset: Vec2, radius: f32 },
     AABB { min: Vec2, max: Vec2 },
+    // connected line segments through the points, meant for static level geometry.
+    // chains are two-sided and have no area
+    Chain { points: Vec<Vec2> },
+}
+
+/// The variant of a collider without its data, for branching on the shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ColliderKind {
+    Circle,
+    AABB,
+    Chain,
 }
 
 /// returns the point on the aabb surface that is nearest to the given point
-fn point_aabb_nearest_point(point: Vec2, aabb: &Collider, body: &RigidBody2D) -> Vec2 {
+/// None if the collider is not an aabb
+fn point_aabb_nearest_point(point: Vec2, aabb: &Collider, body: &RigidBody2D) -> Option<Vec2> {
     if let Collider::AABB { min, max } = aabb {
         let world_min = body.position + *min;
         let world_max = body.position + *max;
@@ -19,29 +33,51 @@ fn point_aabb_nearest_point(point: Vec2, aabb: &Collider, body: &RigidBody2D) ->
             nearest_point.x = point.x
         } else if point.x < world_min.x {
             nearest_point.x = world_min.x;
-        } else if point.x > world_max.x {
-            nearest_point.x = world_max.x;
-        } else if point.x - world_min.x > world_max.x - point.x {
-            nearest_point.x = world_max.x;
         } else {
-            nearest_point.x = world_min.x
+            nearest_point.x = world_max.x;
         }
 
         if point.y <= world_max.y && point.y >= world_min.y {
             nearest_point.y = point.y
         } else if point.y < world_min.y {
             nearest_point.y = world_min.y;
-        } else if point.y > world_max.y {
-            nearest_point.y = world_max.y;
-        } else if point.y - world_min.y > world_max.y - point.y {
-            nearest_point.y = world_max.y;
         } else {
-            nearest_point.y = world_min.y
+            nearest_point.y = world_max.y;
         }
 
-        nearest_point
+        Some(nearest_point)
     } else {
-        panic!();
+        None
+    }
+}
+
+// https://www.r-5.org/files/books/computers/algo-list/realtime-3d/Christer_Ericson-Real-Time_Collision_Detection-EN.pdf
+// None if the collider is not an aabb
+fn sq_dist_point_aabb(point: Vec2, aabb: &Collider, body_pos: Vec2) -> Option<f32> {
+    if let Collider::AABB { min, max } = aabb {
+        let world_min = body_pos + *min;
+        let world_max = body_pos + *max;
+        let mut sq_dist: f32 = 0.0;
+
+        let v = point.x;
+        if v < world_min.x {
+            sq_dist += (world_min.x - v) * (world_min.x - v);
+        }
+        if v > world_max.x {
+            sq_dist += (v - world_max.x) * (v - world_max.x);
+        }
+
+        let v = point.y;
+        if v < world_min.y {
+            sq_dist += (world_min.y - v) * (world_min.y - v);
+        }
+        if v > world_max.y {
+            sq_dist += (v - world_max.y) * (v - world_max.y);
+        }
+
+        Some(sq_dist)
+    } else {
+        None
     }
 }
 
@@ -64,9 +100,7 @@ fn test_aabb_circle(
         aabb_index,
         circle_index,
     );
-    let Some(mut contact) = con else {
-        return None;
-    };
+    let mut contact = con?;
     contact.normal *= -1.0;
     Some(contact)
 }
@@ -82,10 +116,10 @@ fn test_circle_aabb(
     match (circle, aabb) {
         (Collider::Circle { radius, .. }, Collider::AABB { min, max }) => {
             // Get the circle's world position.
-            let circle_world_pos = circle.world_circle(circle_body.position).unwrap();
+            let circle_world_pos = circle.world_circle(circle_body.position)?;
             // Compute the squared distance from the circle's center to the AABB.
             let nearest_point_to_center =
-                point_aabb_nearest_point(circle_world_pos, aabb, aabb_body);
+                point_aabb_nearest_point(circle_world_pos, aabb, aabb_body)?;
 
             let dist = Vec2::distance(nearest_point_to_center, circle_world_pos);
             let collision_vector = nearest_point_to_center - circle_world_pos;
@@ -93,8 +127,8 @@ fn test_circle_aabb(
             let world_min = *min + aabb_body.position;
             let world_max = *max + aabb_body.position;
 
-            let mut normal = collision_vector;
-            if is_close_to_zero(normal) {
+            // the center lies inside of the box
+            if is_close_to_zero(collision_vector) {
                 let distance_left = (circle_world_pos.x - world_min.x).abs();
                 let distance_right = (circle_world_pos.x - world_max.x).abs();
                 let distance_bottom = (circle_world_pos.y - world_min.y).abs();
@@ -105,21 +139,40 @@ fn test_circle_aabb(
                     f32::min(distance_right, f32::min(distance_bottom, distance_top)),
                 );
 
+                // the circle gets pushed out through the nearest face, so the normal
+                // points from the circle into the box, against the normal of that face.
+                // The contact lies on that face, right next to the center
+                let mut normal = Vec2::ZERO;
+                let mut point = circle_world_pos;
                 if min_distance == distance_left {
-                    normal = -Vec2::X;
+                    normal = Vec2::X;
+                    point.x = world_min.x;
                 }
                 if min_distance == distance_right {
-                    normal = Vec2::X;
+                    normal = -Vec2::X;
+                    point.x = world_max.x;
                 }
                 if min_distance == distance_bottom {
-                    normal = -Vec2::Y;
+                    normal = Vec2::Y;
+                    point = vec2(circle_world_pos.x, world_min.y);
                 }
                 if min_distance == distance_top {
-                    normal = Vec2::Y;
+                    normal = -Vec2::Y;
+                    point = vec2(circle_world_pos.x, world_max.y);
                 }
+
+                // the whole circle has to get out through the face
+                return Some(Contact {
+                    point,
+                    pen_depth: *radius + min_distance,
+                    normal,
+                    body_a_index: circle_index,
+                    body_b_index: aabb_index,
+                    ..Default::default()
+                });
             }
 
-            normal = normal.normalize();
+            let normal = collision_vector.normalize();
 
             // if a collision has occured, compute how it actually happened
             if dist < *radius {
@@ -129,6 +182,7 @@ fn test_circle_aabb(
                     normal,
                     body_a_index: circle_index,
                     body_b_index: aabb_index,
+                    ..Default::default()
                 })
             } else {
                 None
@@ -138,8 +192,243 @@ fn test_circle_aabb(
     }
 }
 
+fn closest_point_on_segment(point: Vec2, start: Vec2, end: Vec2) -> Vec2 {
+    let segment = end - start;
+    let length_sq = segment.length_squared();
+    if length_sq == 0.0 {
+        return start;
+    }
+    let t = ((point - start).dot(segment) / length_sq).clamp(0.0, 1.0);
+    start + segment * t
+}
+
+/// Tests a circle against a convex polygon given by its world vertices in counter-clockwise order,
+/// like the ones `Collider::to_polygon` produces. Returns the contact point on the surface of the
+/// polygon, the normal pointing from the polygon toward the circle and the penetration depth.
+/// A center inside the polygon is pushed out through the nearest edge, like with AABBs.
+pub fn circle_polygon_contact(
+    center: Vec2,
+    radius: f32,
+    vertices: &[Vec2],
+) -> Option<(Vec2, Vec2, f32)> {
+    if vertices.len() < 3 {
+        return None;
+    }
+    let edges = || (0..vertices.len()).map(|i| (vertices[i], vertices[(i + 1) % vertices.len()]));
+
+    // the edge the center lies furthest outside of, or least inside of
+    let (separation, normal) = edges()
+        .filter_map(|(start, end)| {
+            // the interior is to the left of counter-clockwise edges, so outwards is to the right
+            let normal = -(end - start).perp().try_normalize()?;
+            Some((normal.dot(center - start), normal))
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))?;
+    if separation > radius {
+        return None;
+    }
+
+    if separation <= 0.0 {
+        let point = center - normal * separation;
+        return Some((point, normal, radius - separation));
+    }
+
+    // outside, but the edge may still be too far if the closest feature is a corner
+    let point = edges()
+        .map(|(start, end)| closest_point_on_segment(center, start, end))
+        .min_by(|a, b| {
+            a.distance_squared(center)
+                .total_cmp(&b.distance_squared(center))
+        })?;
+    let distance = point.distance(center);
+    if distance > radius {
+        return None;
+    }
+    let normal = (center - point).try_normalize().unwrap_or(normal);
+    Some((point, normal, radius - distance))
+}
+
+/// the segments of a chain in world coordinates
+fn chain_segments(points: &[Vec2], body_pos: Vec2) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    points
+        .windows(2)
+        .map(move |pair| (body_pos + pair[0], body_pos + pair[1]))
+}
+
+/// Returns the point on the chain that is closest to the given point
+/// together with the segment it lies on.
+fn closest_point_on_chain(
+    point: Vec2,
+    points: &[Vec2],
+    body_pos: Vec2,
+) -> Option<(Vec2, (Vec2, Vec2))> {
+    chain_segments(points, body_pos)
+        .map(|(start, end)| (closest_point_on_segment(point, start, end), (start, end)))
+        .min_by(|(a, _), (b, _)| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        })
+}
+
+/// Tests a circle against a chain. The circle is tested against the closest point of the whole
+/// chain, so a vertex shared by two segments behaves like a smooth surface.
+/// The normal points from the chain towards the circle.
+fn test_chain_circle(
+    points: &[Vec2],
+    chain_pos: Vec2,
+    center: Vec2,
+    radius: f32,
+) -> Option<(Vec2, Vec2, f32)> {
+    let (point, (start, end)) = closest_point_on_chain(center, points, chain_pos)?;
+    let dist = point.distance(center);
+    if dist >= radius {
+        return None;
+    }
+
+    let normal = if is_close_to_zero(center - point) {
+        // the center lies on the chain, so push it out along the segment
+        (end - start).perp().normalize_or_zero()
+    } else {
+        (center - point) / dist
+    };
+    Some((point, normal, radius - dist))
+}
+
+/// Tests an aabb against a single segment. The x, y and segment normal axes are used to detect the
+/// overlap, but the box is only ever pushed along the segment normal. This prevents ghost
+/// collisions where a box sliding over a vertex shared by two segments catches on the
+/// end of the next segment.
+/// The normal points from the segment towards the box.
+fn test_segment_aabb(
+    start: Vec2,
+    end: Vec2,
+    world_min: Vec2,
+    world_max: Vec2,
+) -> Option<(Vec2, Vec2, f32)> {
+    let segment_min = start.min(end);
+    let segment_max = start.max(end);
+    if segment_max.cmplt(world_min).any() || segment_min.cmpgt(world_max).any() {
+        return None;
+    }
+
+    let direction = end - start;
+    if direction.length_squared() == 0.0 {
+        return None;
+    }
+
+    let center = (world_min + world_max) * 0.5;
+    let half_extents = (world_max - world_min) * 0.5;
+
+    let mut normal = direction.perp().normalize();
+    if (center - start).dot(normal) < 0.0 {
+        normal = -normal;
+    }
+
+    // how far the box reaches from its center along the normal
+    let reach = half_extents.x * normal.x.abs() + half_extents.y * normal.y.abs();
+    let pen_depth = reach - (center - start).dot(normal);
+    if pen_depth <= 0.0 {
+        return None;
+    }
+
+    let point = closest_point_on_segment(center, start, end);
+    Some((point, normal, pen_depth))
+}
+
+/// Tests an aabb against every segment of a chain and returns the deepest contact.
+/// The normal points from the chain towards the box.
+fn test_chain_aabb(
+    points: &[Vec2],
+    chain_pos: Vec2,
+    world_min: Vec2,
+    world_max: Vec2,
+) -> Option<(Vec2, Vec2, f32)> {
+    chain_segments(points, chain_pos)
+        .filter_map(|(start, end)| test_segment_aabb(start, end, world_min, world_max))
+        .max_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+}
+
+fn segment_aabb_distance(start: Vec2, end: Vec2, world_min: Vec2, world_max: Vec2) -> f32 {
+    if let Some((_, _, pen_depth)) = test_segment_aabb(start, end, world_min, world_max) {
+        return -pen_depth;
+    }
+
+    // if they don't overlap the closest points are an end of the segment or a corner of the box
+    let endpoint_distance = [start, end]
+        .into_iter()
+        .map(|point| point.distance(point.clamp(world_min, world_max)))
+        .fold(f32::INFINITY, f32::min);
+    let corners = [
+        world_min,
+        vec2(world_max.x, world_min.y),
+        world_max,
+        vec2(world_min.x, world_max.y),
+    ];
+    let corner_distance = corners
+        .into_iter()
+        .map(|corner| corner.distance(closest_point_on_segment(corner, start, end)))
+        .fold(f32::INFINITY, f32::min);
+    f32::min(endpoint_distance, corner_distance)
+}
+
+/// the point at the fraction `t` of the total length of the polyline
+fn point_along_polyline(points: &[Vec2], t: f32) -> Vec2 {
+    let total_length: f32 = points
+        .windows(2)
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum();
+    let mut remaining = t * total_length;
+    for pair in points.windows(2) {
+        let length = pair[0].distance(pair[1]);
+        if remaining <= length && length > 0.0 {
+            return pair[0].lerp(pair[1], remaining / length);
+        }
+        remaining -= length;
+    }
+    points.last().copied().unwrap_or(Vec2::ZERO)
+}
+
+fn segments_intersect(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> bool {
+    let side = |p: Vec2, (start, end): (Vec2, Vec2)| (end - start).perp_dot(p - start);
+    side(b.0, a) * side(b.1, a) <= 0.0 && side(a.0, b) * side(a.1, b) <= 0.0
+}
+
+fn segment_distance(a: (Vec2, Vec2), b: (Vec2, Vec2)) -> f32 {
+    if segments_intersect(a, b) {
+        return 0.0;
+    }
+    [
+        a.0.distance(closest_point_on_segment(a.0, b.0, b.1)),
+        a.1.distance(closest_point_on_segment(a.1, b.0, b.1)),
+        b.0.distance(closest_point_on_segment(b.0, a.0, a.1)),
+        b.1.distance(closest_point_on_segment(b.1, a.0, a.1)),
+    ]
+    .into_iter()
+    .fold(f32::INFINITY, f32::min)
+}
+
 impl Collider {
-    // transform the position from local collider coordinates to world coodinates (relative to some body)
+    pub fn kind(&self) -> ColliderKind {
+        match self {
+            Collider::Circle { .. } => ColliderKind::Circle,
+            Collider::AABB { .. } => ColliderKind::AABB,
+            Collider::Chain { .. } => ColliderKind::Chain,
+        }
+    }
+
+    pub fn is_circle(&self) -> bool {
+        self.kind() == ColliderKind::Circle
+    }
+
+    pub fn is_aabb(&self) -> bool {
+        self.kind() == ColliderKind::AABB
+    }
+
+    pub fn is_chain(&self) -> bool {
+        self.kind() == ColliderKind::Chain
+    }
+
+    // transform the position from local collider coordinates to world coordinates (relative to some body)
     pub fn world_aabb(&self, body_pos: Vec2) -> Option<(Vec2, Vec2)> {
         match self {
             Collider::AABB { min, max } => Some((body_pos + *min, body_pos + *max)),
@@ -154,6 +443,276 @@ impl Collider {
         }
     }
 
+    /// the width and height of the collider's bounding box
+    pub fn extents(&self) -> Vec2 {
+        let (min, max) = self.world_bounds(Vec2::ZERO);
+        max - min
+    }
+
+    /// The center of the shape relative to the body, which is its center of mass for a uniform
+    /// density. For chains it is the average of their points.
+    pub fn centroid(&self) -> Vec2 {
+        match self {
+            Collider::Circle { offset, .. } => *offset,
+            Collider::AABB { min, max } => (*min + *max) * 0.5,
+            Collider::Chain { points } => {
+                points.iter().copied().sum::<Vec2>() / points.len().max(1) as f32
+            }
+        }
+    }
+
+    /// The radius of a circle around the center of the collider that contains all of it.
+    /// That is the radius for circles and half of the diagonal for boxes and chains.
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            Collider::Circle { radius, .. } => *radius,
+            Collider::AABB { .. } | Collider::Chain { .. } => 0.5 * self.extents().length(),
+        }
+    }
+
+    /// the axis-aligned bounding box of the collider in world coordinates
+    pub fn world_bounds(&self, body_pos: Vec2) -> (Vec2, Vec2) {
+        match self {
+            Collider::Circle { offset, radius } => {
+                let center = body_pos + *offset;
+                (center - Vec2::splat(*radius), center + Vec2::splat(*radius))
+            }
+            Collider::AABB { min, max } => (body_pos + *min, body_pos + *max),
+            Collider::Chain { points } => {
+                let min = points.iter().copied().fold(Vec2::INFINITY, Vec2::min);
+                let max = points.iter().copied().fold(Vec2::NEG_INFINITY, Vec2::max);
+                if points.is_empty() {
+                    (body_pos, body_pos)
+                } else {
+                    (body_pos + min, body_pos + max)
+                }
+            }
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        match self {
+            Collider::Circle { radius, .. } => std::f32::consts::PI * radius * radius,
+            Collider::AABB { min, max } => {
+                let size = (*max - *min).abs();
+                size.x * size.y
+            }
+            Collider::Chain { .. } => 0.0,
+        }
+    }
+
+    /// the mass of the collider if it is filled uniformly with the given density
+    pub fn mass(&self, density: f32) -> f32 {
+        self.area() * density
+    }
+
+    /// the moment of inertia around the center of the shape for the given mass
+    pub fn inertia(&self, mass: f32) -> f32 {
+        match self {
+            // I = 1/2 * m * r²
+            Collider::Circle { radius, .. } => 0.5 * mass * radius * radius,
+            // I = 1/12 * m * (w² + h²)
+            Collider::AABB { min, max } => {
+                let size = (*max - *min).abs();
+                (1.0 / 12.0) * mass * (size.x * size.x + size.y * size.y)
+            }
+            // a chain has no area, so it can't be rotated around anything meaningful
+            Collider::Chain { .. } => 0.0,
+        }
+    }
+
+    /// Converts the collider into a list of local vertices in counter-clockwise order.
+    /// Circles are approximated by a regular polygon with `segments` vertices (at least 3),
+    /// AABBs always produce their 4 corners and chains their points.
+    pub fn to_polygon(&self, segments: usize) -> Vec<Vec2> {
+        match self {
+            Collider::Circle { offset, radius } => {
+                let segments = segments.max(3);
+                (0..segments)
+                    .map(|i| {
+                        let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+                        *offset + Vec2::from_angle(angle) * *radius
+                    })
+                    .collect()
+            }
+            Collider::AABB { min, max } => vec![*min, vec2(max.x, min.y), *max, vec2(min.x, max.y)],
+            Collider::Chain { points } => points.clone(),
+        }
+    }
+
+    /// Maps `t` in [0, 1) to a point on the surface of the collider in world coordinates, going
+    /// counter-clockwise around circles (starting at +x) and boxes (starting at the min corner) and
+    /// from the first to the last point of chains. Points are spread evenly by length, so a random
+    /// `t` picks any part of the surface with the same probability. `t` outside of [0, 1) wraps.
+    pub fn sample_surface(&self, body: &RigidBody2D, t: f32) -> Vec2 {
+        let t = t.rem_euclid(1.0);
+        match self {
+            Collider::Circle { offset, radius } => {
+                body.position + *offset + Vec2::from_angle(t * std::f32::consts::TAU) * *radius
+            }
+            Collider::AABB { .. } => {
+                let mut corners = self.to_polygon(4);
+                corners.push(corners[0]);
+                body.position + point_along_polyline(&corners, t)
+            }
+            Collider::Chain { points } => body.position + point_along_polyline(points, t),
+        }
+    }
+
+    /// The point of the collider in world coordinates that lies farthest in direction `dir`,
+    /// the building block of GJK and EPA. The shape is turned by the angle of the body around
+    /// `position`, so a turned box gives its turned corners. For a chain it is the farthest of
+    /// its points, as if it was its convex hull.
+    pub fn support(&self, body: &RigidBody2D, dir: Vec2) -> Vec2 {
+        let rotation = Vec2::from_angle(body.angle);
+        // the direction in the coordinates of the unturned shape
+        let local_dir = Vec2::from_angle(-body.angle).rotate(dir);
+        let to_world = |local: Vec2| body.position + rotation.rotate(local);
+        match self {
+            Collider::Circle { offset, radius } => {
+                to_world(*offset) + dir.normalize_or_zero() * *radius
+            }
+            Collider::AABB { min, max } => {
+                let x = if local_dir.x >= 0.0 { max.x } else { min.x };
+                let y = if local_dir.y >= 0.0 { max.y } else { min.y };
+                to_world(vec2(x, y))
+            }
+            Collider::Chain { points } => {
+                let farthest = points
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| a.dot(local_dir).total_cmp(&b.dot(local_dir)))
+                    .unwrap_or(Vec2::ZERO);
+                to_world(farthest)
+            }
+        }
+    }
+
+    /// true if the world point lies inside or on the surface of the collider
+    pub fn contains_point(&self, body: &RigidBody2D, world_point: Vec2) -> bool {
+        match self {
+            Collider::Circle { offset, radius } => {
+                let center = body.position + *offset;
+                center.distance_squared(world_point) <= radius * radius
+            }
+            Collider::AABB { min, max } => {
+                let (world_min, world_max) = (body.position + *min, body.position + *max);
+                world_point.cmpge(world_min).all() && world_point.cmple(world_max).all()
+            }
+            Collider::Chain { .. } => false,
+        }
+    }
+
+    /// Returns the signed distance from `world_point` to the surface of the collider.
+    /// The distance is negative if the point lies inside the collider and positive outside.
+    pub fn signed_distance(&self, body: &RigidBody2D, world_point: Vec2) -> f32 {
+        self.signed_distance_at(body.position, world_point)
+    }
+
+    fn signed_distance_at(&self, body_pos: Vec2, world_point: Vec2) -> f32 {
+        match self {
+            Collider::Circle { offset, radius } => {
+                let center = body_pos + *offset;
+                center.distance(world_point) - radius
+            }
+            Collider::AABB { min, max } => {
+                if let Some(sq_dist) = sq_dist_point_aabb(world_point, self, body_pos)
+                    && sq_dist > 0.0
+                {
+                    return sq_dist.sqrt();
+                }
+
+                // the point is inside, so the distance is the one to the closest face
+                let (world_min, world_max) = (body_pos + *min, body_pos + *max);
+                let distance_x = f32::min(world_point.x - world_min.x, world_max.x - world_point.x);
+                let distance_y = f32::min(world_point.y - world_min.y, world_max.y - world_point.y);
+                -f32::min(distance_x, distance_y)
+            }
+            // a chain has no inside, so the distance is never negative
+            Collider::Chain { points } => closest_point_on_chain(world_point, points, body_pos)
+                .map_or(f32::INFINITY, |(point, _)| point.distance(world_point)),
+        }
+    }
+
+    /// Returns the distance between the surfaces of two colliders.
+    /// The distance is negative if the colliders overlap.
+    pub fn distance(&self, body: &RigidBody2D, other: &Collider, other_body: &RigidBody2D) -> f32 {
+        self.distance_at(body.position, other, other_body.position)
+    }
+
+    fn distance_at(&self, body_pos: Vec2, other: &Collider, other_pos: Vec2) -> f32 {
+        match (self, other) {
+            (
+                Collider::Circle {
+                    offset: offset_a,
+                    radius: radius_a,
+                },
+                Collider::Circle {
+                    offset: offset_b,
+                    radius: radius_b,
+                },
+            ) => {
+                let pos_a = body_pos + *offset_a;
+                let pos_b = other_pos + *offset_b;
+                pos_a.distance(pos_b) - radius_a - radius_b
+            }
+            (Collider::Circle { offset, radius }, Collider::AABB { .. }) => {
+                let center = body_pos + *offset;
+                other.signed_distance_at(other_pos, center) - radius
+            }
+            (Collider::AABB { .. }, Collider::Circle { .. }) => {
+                other.distance_at(other_pos, self, body_pos)
+            }
+            (
+                Collider::AABB {
+                    min: local_min_a,
+                    max: local_max_a,
+                },
+                Collider::AABB {
+                    min: local_min_b,
+                    max: local_max_b,
+                },
+            ) => {
+                let (min_a, max_a) = (body_pos + *local_min_a, body_pos + *local_max_a);
+                let (min_b, max_b) = (other_pos + *local_min_b, other_pos + *local_max_b);
+
+                // the gap along each axis, negative if the boxes overlap on that axis
+                let gap_x = f32::max(min_b.x - max_a.x, min_a.x - max_b.x);
+                let gap_y = f32::max(min_b.y - max_a.y, min_a.y - max_b.y);
+
+                if gap_x > 0.0 && gap_y > 0.0 {
+                    vec2(gap_x, gap_y).length()
+                } else {
+                    f32::max(gap_x, gap_y)
+                }
+            }
+            (Collider::Circle { offset, radius }, Collider::Chain { .. }) => {
+                let center = body_pos + *offset;
+                other.signed_distance_at(other_pos, center) - radius
+            }
+            (Collider::AABB { min, max }, Collider::Chain { points }) => {
+                let (world_min, world_max) = (body_pos + *min, body_pos + *max);
+                chain_segments(points, other_pos)
+                    .map(|(start, end)| segment_aabb_distance(start, end, world_min, world_max))
+                    .fold(f32::INFINITY, f32::min)
+            }
+            (Collider::Chain { .. }, Collider::Circle { .. } | Collider::AABB { .. }) => {
+                other.distance_at(other_pos, self, body_pos)
+            }
+            (Collider::Chain { points: points_a }, Collider::Chain { points: points_b }) => {
+                let mut distance = f32::INFINITY;
+                for segment_a in chain_segments(points_a, body_pos) {
+                    for segment_b in chain_segments(points_b, other_pos) {
+                        distance = distance.min(segment_distance(segment_a, segment_b));
+                    }
+                }
+                distance
+            }
+        }
+    }
+
+    /// Returns the contact between this collider and `collider_b` if they overlap.
+    /// The normal of the contact always points from this collider toward `collider_b`.
     pub fn collides_with(
         &self, // collider_a
         body_a: &RigidBody2D,
@@ -172,20 +731,28 @@ impl Collider {
                     radius: radius_b, ..
                 },
             ) => {
-                let pos_a = self.world_circle(body_a.position).unwrap();
-                let pos_b = collider_b.world_circle(body_b.position).unwrap();
+                let pos_a = self.world_circle(body_a.position)?;
+                let pos_b = collider_b.world_circle(body_b.position)?;
                 let position_difference = pos_b - pos_a;
 
                 // this can be used to calculate the distance
                 let dist = pos_a.distance(pos_b);
 
                 if dist < radius_a + radius_b {
-                    let normal = position_difference / dist;
+                    // concentric circles have no preferred direction, so just pick one
+                    let normal = if is_close_to_zero(position_difference) {
+                        Vec2::Y
+                    } else {
+                        position_difference / dist
+                    };
 
-                    // compute the middle_point between the surfaces of circles
-                    let surface_a = pos_a + normal * *radius_a;
-                    let surface_b = pos_b - normal * *radius_b;
-                    let point = (surface_a + surface_b) * 0.5;
+                    // along the line between the centers (measured from pos_a), circle a covers
+                    // [-radius_a, radius_a] and circle b covers [dist - radius_b, dist + radius_b].
+                    // the contact point is the middle of the overlap of both, so it stays inside
+                    // both circles even if one of them contains the center of the other
+                    let overlap_start = f32::max(-radius_a, dist - radius_b);
+                    let overlap_end = f32::min(*radius_a, dist + radius_b);
+                    let point = pos_a + normal * (overlap_start + overlap_end) * 0.5;
 
                     Some(Contact {
                         point,
@@ -193,6 +760,7 @@ impl Collider {
                         pen_depth: radius_a + radius_b - dist,
                         body_a_index,
                         body_b_index,
+                        ..Default::default()
                     })
                 } else {
                     None
@@ -211,9 +779,68 @@ impl Collider {
                 test_circle_aabb(self, collider_b, body_a, body_b, body_a_index, body_b_index)
             }
 
+            (Collider::Chain { points }, Collider::Circle { radius, .. }) => {
+                let center = collider_b.world_circle(body_b.position)?;
+                let (point, normal, pen_depth) =
+                    test_chain_circle(points, body_a.position, center, *radius)?;
+                Some(Contact {
+                    point,
+                    normal,
+                    pen_depth,
+                    body_a_index,
+                    body_b_index,
+                    ..Default::default()
+                })
+            }
+
+            (Collider::Circle { radius, .. }, Collider::Chain { points }) => {
+                let center = self.world_circle(body_a.position)?;
+                let (point, normal, pen_depth) =
+                    test_chain_circle(points, body_b.position, center, *radius)?;
+                Some(Contact {
+                    point,
+                    normal: -normal,
+                    pen_depth,
+                    body_a_index,
+                    body_b_index,
+                    ..Default::default()
+                })
+            }
+
+            (Collider::Chain { points }, Collider::AABB { .. }) => {
+                let (world_min, world_max) = collider_b.world_aabb(body_b.position)?;
+                let (point, normal, pen_depth) =
+                    test_chain_aabb(points, body_a.position, world_min, world_max)?;
+                Some(Contact {
+                    point,
+                    normal,
+                    pen_depth,
+                    body_a_index,
+                    body_b_index,
+                    ..Default::default()
+                })
+            }
+
+            (Collider::AABB { .. }, Collider::Chain { points }) => {
+                let (world_min, world_max) = self.world_aabb(body_a.position)?;
+                let (point, normal, pen_depth) =
+                    test_chain_aabb(points, body_b.position, world_min, world_max)?;
+                Some(Contact {
+                    point,
+                    normal: -normal,
+                    pen_depth,
+                    body_a_index,
+                    body_b_index,
+                    ..Default::default()
+                })
+            }
+
+            // chains are static level geometry, so they never need to be pushed apart
+            (Collider::Chain { .. }, Collider::Chain { .. }) => None,
+
             (Collider::AABB { .. }, Collider::AABB { .. }) => {
-                let min_max_a = self.world_aabb(body_a.position).unwrap();
-                let min_max_b = collider_b.world_aabb(body_b.position).unwrap();
+                let min_max_a = self.world_aabb(body_a.position)?;
+                let min_max_b = collider_b.world_aabb(body_b.position)?;
 
                 let min_a = min_max_a.0;
                 let max_a = min_max_a.1;
@@ -260,9 +887,9 @@ impl Collider {
                         let left_penetration = max_a.x - min_b.x;
                         let right_penetration = max_b.x - min_a.x;
 
-                        // the object needs to be pushed to the left
-                        // because the left penetration is smaller
-                        if left_penetration < right_penetration {
+                        // the object needs to be pushed to the right
+                        // because the right penetration is smaller
+                        if right_penetration < left_penetration {
                             normal *= -1.0;
                         }
                     }
@@ -273,6 +900,7 @@ impl Collider {
                         normal,
                         body_a_index,
                         body_b_index,
+                        ..Default::default()
                     });
                 }
                 None
@@ -280,3 +908,846 @@ impl Collider {
         }
     }
 }
+
+/// Computes the time of impact of two moving colliders using conservative advancement.
+/// Both bodies are moved along their current velocity and advanced by the distance between
+/// them divided by their relative speed until they touch. Every advance goes `TOLERANCE`
+/// further, so the colliders end up overlapping by up to that much and the collision detection
+/// of the next step finds their contact. Otherwise bodies that almost touch would be stopped
+/// just short of each other step after step.
+/// Returns the fraction of `dt` at which the colliders first touch or None if they don't touch within `dt`.
+pub fn toi(
+    a: &Collider,
+    body_a: &RigidBody2D,
+    b: &Collider,
+    body_b: &RigidBody2D,
+    dt: f32,
+) -> Option<f32> {
+    const TOLERANCE: f32 = 0.001;
+    const MAX_ITERATIONS: usize = 32;
+
+    if dt <= 0.0 {
+        return None;
+    }
+
+    // the shapes don't rotate, so the relative speed bounds how fast the distance can shrink
+    let relative_speed = (body_b.vel - body_a.vel).length();
+
+    let mut t = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        let pos_a = body_a.position + body_a.vel * t;
+        let pos_b = body_b.position + body_b.vel * t;
+        let distance = a.distance_at(pos_a, b, pos_b);
+
+        if distance <= 0.0 {
+            return Some(t / dt);
+        }
+        if relative_speed <= f32::EPSILON {
+            return None;
+        }
+
+        t += (distance + TOLERANCE) / relative_speed;
+        if t > dt {
+            return None;
+        }
+    }
+
+    // conservative advancement overshoots by at most `TOLERANCE`, so the bodies are about to touch
+    Some(t / dt)
+}
+
+/// Returns the distance between two convex colliders using GJK, 0 if they touch or overlap.
+/// Unlike `Collider::distance` it only needs `Collider::support`, so it works the same way for
+/// every pair of shapes. Chains are treated as their convex hull.
+pub fn gjk_distance(a: &Collider, body_a: &RigidBody2D, b: &Collider, body_b: &RigidBody2D) -> f32 {
+    match gjk(a, body_a, b, body_b) {
+        Gjk::Separated(closest) => closest.length(),
+        Gjk::Overlapping => 0.0,
+    }
+}
+
+/// Returns the normal pointing from a toward b and the depth of the penetration of two
+/// overlapping convex colliders using EPA, None if they don't overlap or have no area. Like for a `Contact`,
+/// moving b by `normal * depth` separates the two. Chains are treated as their convex hull.
+pub fn epa_penetration(
+    a: &Collider,
+    body_a: &RigidBody2D,
+    b: &Collider,
+    body_b: &RigidBody2D,
+) -> Option<(Vec2, f32)> {
+    const MAX_ITERATIONS: usize = 32;
+    // stop once the closest edge can't be pushed out further than this
+    const TOLERANCE: f32 = 1e-4;
+
+    if let Gjk::Separated(_) = gjk(a, body_a, b, body_b) {
+        return None;
+    }
+    let support = |dir: Vec2| minkowski_support(a, body_a, b, body_b, dir);
+    // the polytope grows towards the boundary of the difference until its edge closest to the
+    // origin lies on it. That edge is where the colliders are the least deep inside each other.
+    // Its edges go counter-clockwise, so the outside is on their right
+    let mut polytope = initial_polytope(support);
+    if polytope.len() < 3 {
+        // the difference has no area, like the one of two parallel chains
+        return None;
+    }
+
+    let mut penetration = (Vec2::ZERO, 0.0);
+    for _ in 0..MAX_ITERATIONS {
+        let count = polytope.len();
+        let (index, normal, distance) = (0..count)
+            .filter_map(|i| {
+                let (start, end) = (polytope[i], polytope[(i + 1) % count]);
+                let normal = -(end - start).perp().try_normalize()?;
+                Some((i, normal, normal.dot(start)))
+            })
+            .min_by(|x, y| x.2.total_cmp(&y.2))?;
+        penetration = (normal, distance.max(0.0));
+
+        let new_point = support(normal);
+        if new_point.dot(normal) - distance <= TOLERANCE {
+            break;
+        }
+        polytope.insert(index + 1, new_point);
+    }
+    Some(penetration)
+}
+
+/// The result of running GJK on the Minkowski difference of two colliders.
+enum Gjk {
+    // the point of the difference that is closest to the origin
+    Separated(Vec2),
+    // the difference contains the origin or touches it
+    Overlapping,
+}
+
+fn minkowski_support(
+    a: &Collider,
+    body_a: &RigidBody2D,
+    b: &Collider,
+    body_b: &RigidBody2D,
+    dir: Vec2,
+) -> Vec2 {
+    a.support(body_a, dir) - b.support(body_b, -dir)
+}
+
+fn gjk(a: &Collider, body_a: &RigidBody2D, b: &Collider, body_b: &RigidBody2D) -> Gjk {
+    const MAX_ITERATIONS: usize = 32;
+    // stop once a new support point brings the closest point less than this fraction closer,
+    // round shapes would need an infinite number of iterations otherwise
+    const RELATIVE_TOLERANCE: f32 = 1e-5;
+    const TOUCHING_DISTANCE: f32 = 1e-6;
+
+    // the colliders overlap if their Minkowski difference a - b contains the origin, otherwise
+    // their distance is the distance from the origin to it
+    let support = |dir: Vec2| minkowski_support(a, body_a, b, body_b, dir);
+
+    let mut closest = support(body_b.position - body_a.position);
+    let mut simplex = vec![closest];
+    for _ in 0..MAX_ITERATIONS {
+        if closest.length() <= TOUCHING_DISTANCE {
+            return Gjk::Overlapping;
+        }
+        let new_point = support(-closest);
+        // nothing in the difference is much closer to the origin than what was found already
+        let progress = closest.dot(closest) - closest.dot(new_point);
+        if progress <= RELATIVE_TOLERANCE * closest.dot(closest) {
+            break;
+        }
+        simplex.push(new_point);
+        match reduce_simplex(&mut simplex) {
+            Some(point) => closest = point,
+            None => return Gjk::Overlapping,
+        }
+    }
+    Gjk::Separated(closest)
+}
+
+/// A convex polygon inside the Minkowski difference to start EPA with, made of the support
+/// points in eight directions. Going around the directions in order keeps it convex.
+fn initial_polytope(support: impl Fn(Vec2) -> Vec2) -> Vec<Vec2> {
+    let mut polytope: Vec<Vec2> = vec![];
+    for i in 0..8 {
+        let point = support(Vec2::from_angle(i as f32 * std::f32::consts::FRAC_PI_4));
+        // neighboring directions can share a corner
+        if polytope
+            .last()
+            .is_none_or(|last| last.distance_squared(point) > f32::EPSILON)
+        {
+            polytope.push(point);
+        }
+    }
+    if polytope.len() > 1
+        && polytope[0].distance_squared(polytope[polytope.len() - 1]) <= f32::EPSILON
+    {
+        polytope.pop();
+    }
+    polytope
+}
+
+/// Reduces the simplex to the vertices that are needed for its point closest to the origin and
+/// returns that point. Returns None if the simplex is a triangle that contains the origin.
+fn reduce_simplex(simplex: &mut Vec<Vec2>) -> Option<Vec2> {
+    match simplex[..] {
+        [point] => Some(point),
+        [start, end] => {
+            let (point, kept) = closest_to_origin_on_segment(start, end);
+            *simplex = kept;
+            Some(point)
+        }
+        [a, b, c] => {
+            let edges = [(a, b), (b, c), (c, a)];
+            let area = (b - a).perp_dot(c - a);
+            let scale = (b - a).length_squared().max((c - a).length_squared());
+            // a flat triangle can't contain the origin, only its edges can touch it
+            if area.abs() > f32::EPSILON * scale {
+                let contains_origin = edges
+                    .iter()
+                    .all(|(start, end)| (*end - *start).perp_dot(-*start) * area.signum() >= 0.0);
+                if contains_origin {
+                    return None;
+                }
+            }
+            let (point, kept) = edges
+                .into_iter()
+                .map(|(start, end)| closest_to_origin_on_segment(start, end))
+                .min_by(|x, y| x.0.length_squared().total_cmp(&y.0.length_squared()))
+                .unwrap();
+            *simplex = kept;
+            Some(point)
+        }
+        _ => unreachable!("a simplex in 2d has at most 3 vertices"),
+    }
+}
+
+/// the point of the segment closest to the origin and the vertices of the segment it needs
+fn closest_to_origin_on_segment(start: Vec2, end: Vec2) -> (Vec2, Vec<Vec2>) {
+    let edge = end - start;
+    let length_squared = edge.length_squared();
+    let t = if length_squared > 0.0 {
+        (-start.dot(edge) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    if t <= 0.0 {
+        (start, vec![start])
+    } else if t >= 1.0 {
+        (end, vec![end])
+    } else {
+        (start + edge * t, vec![start, end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn body_at(position: Vec2) -> RigidBody2D {
+        RigidBody2DBuilder::new().with_position(position).build()
+    }
+
+    struct ExpectedContact {
+        normal: Vec2,
+        point: Vec2,
+        pen_depth: f32,
+    }
+
+    struct Case {
+        name: &'static str,
+        a: Collider,
+        pos_a: Vec2,
+        b: Collider,
+        pos_b: Vec2,
+        expected: Option<ExpectedContact>,
+    }
+
+    fn circle(radius: f32) -> Collider {
+        Collider::Circle {
+            offset: Vec2::ZERO,
+            radius,
+        }
+    }
+
+    fn aabb(min: Vec2, max: Vec2) -> Collider {
+        Collider::AABB { min, max }
+    }
+
+    fn narrow_phase_cases() -> Vec<Case> {
+        let unit_box = aabb(vec2(0.0, 0.0), vec2(2.0, 2.0));
+        let floor = aabb(vec2(-2.0, -1.0), vec2(2.0, 1.0));
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        let ground = Collider::Chain {
+            points: vec![vec2(-2.0, 0.0), vec2(0.0, 0.0), vec2(2.0, 0.0)],
+        };
+
+        vec![
+            Case {
+                name: "circle-circle shallow",
+                a: circle(1.0),
+                pos_a: vec2(0.0, 0.0),
+                b: circle(1.0),
+                pos_b: vec2(1.5, 0.0),
+                expected: Some(ExpectedContact {
+                    normal: vec2(1.0, 0.0),
+                    point: vec2(0.75, 0.0),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "circle-circle separated",
+                a: circle(1.0),
+                pos_a: vec2(0.0, 0.0),
+                b: circle(1.0),
+                pos_b: vec2(0.0, 2.5),
+                expected: None,
+            },
+            Case {
+                name: "circle-aabb face",
+                a: circle(1.0),
+                pos_a: vec2(0.0, 1.5),
+                b: floor.clone(),
+                pos_b: vec2(0.0, 0.0),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, -1.0),
+                    point: vec2(0.0, 1.0),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "circle-aabb corner",
+                a: circle(1.0),
+                pos_a: vec2(2.5, 1.5),
+                b: floor.clone(),
+                pos_b: vec2(0.0, 0.0),
+                expected: Some(ExpectedContact {
+                    normal: vec2(-diagonal, -diagonal),
+                    point: vec2(2.0, 1.0),
+                    pen_depth: 1.0 - diagonal,
+                }),
+            },
+            Case {
+                name: "circle-aabb center inside uses nearest face",
+                a: circle(0.5),
+                pos_a: vec2(0.0, 0.8),
+                b: floor.clone(),
+                pos_b: vec2(0.0, 0.0),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, -1.0),
+                    point: vec2(0.0, 1.0),
+                    pen_depth: 0.7,
+                }),
+            },
+            Case {
+                name: "circle-aabb separated",
+                a: circle(1.0),
+                pos_a: vec2(0.0, 3.0),
+                b: floor.clone(),
+                pos_b: vec2(0.0, 0.0),
+                expected: None,
+            },
+            Case {
+                name: "aabb-circle face",
+                a: floor.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: circle(1.0),
+                pos_b: vec2(0.0, 1.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, 1.0),
+                    point: vec2(0.0, 1.0),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "aabb-aabb b to the right",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: unit_box.clone(),
+                pos_b: vec2(1.5, 0.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(1.0, 0.0),
+                    point: vec2(1.75, 1.25),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "aabb-aabb b to the left",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: unit_box.clone(),
+                pos_b: vec2(-1.5, 0.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(-1.0, 0.0),
+                    point: vec2(0.25, 1.25),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "aabb-aabb b above",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: unit_box.clone(),
+                pos_b: vec2(0.5, 1.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, 1.0),
+                    point: vec2(1.25, 1.75),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "aabb-aabb b below",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: unit_box.clone(),
+                pos_b: vec2(0.5, -1.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, -1.0),
+                    point: vec2(1.25, 0.25),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "aabb-aabb equal overlap resolves along x",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: unit_box.clone(),
+                pos_b: vec2(1.5, 1.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(1.0, 0.0),
+                    point: vec2(1.75, 1.75),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "circle-chain on a shared vertex",
+                a: circle(1.0),
+                pos_a: vec2(0.0, 0.5),
+                b: ground.clone(),
+                pos_b: vec2(0.0, 0.0),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, -1.0),
+                    point: vec2(0.0, 0.0),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "chain-circle below the chain",
+                a: ground.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: circle(1.0),
+                pos_b: vec2(1.0, -0.5),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, -1.0),
+                    point: vec2(1.0, 0.0),
+                    pen_depth: 0.5,
+                }),
+            },
+            Case {
+                name: "aabb-chain on a shared vertex has no ghost normal",
+                a: aabb(vec2(-0.5, 0.0), vec2(0.5, 1.0)),
+                pos_a: vec2(0.0, -0.2),
+                b: ground.clone(),
+                pos_b: vec2(0.0, 0.0),
+                expected: Some(ExpectedContact {
+                    normal: vec2(0.0, -1.0),
+                    point: vec2(0.0, 0.0),
+                    pen_depth: 0.2,
+                }),
+            },
+            Case {
+                name: "aabb-chain separated",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.5),
+                b: ground,
+                pos_b: vec2(0.0, 0.0),
+                expected: None,
+            },
+            Case {
+                name: "aabb-aabb separated",
+                a: unit_box.clone(),
+                pos_a: vec2(0.0, 0.0),
+                b: unit_box,
+                pos_b: vec2(3.0, 0.0),
+                expected: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn narrow_phase_matches_hand_computed_contacts() {
+        for case in narrow_phase_cases() {
+            let body_a = body_at(case.pos_a);
+            let body_b = body_at(case.pos_b);
+            let contact = case.a.collides_with(&body_a, &body_b, &case.b, 0, 1);
+
+            match (contact, case.expected) {
+                (None, None) => {}
+                (Some(contact), Some(expected)) => {
+                    assert_eq!(contact.body_a_index, 0, "{}", case.name);
+                    assert_eq!(contact.body_b_index, 1, "{}", case.name);
+                    assert_relative_eq!(contact.normal.x, expected.normal.x, epsilon = 1e-5);
+                    assert_relative_eq!(contact.normal.y, expected.normal.y, epsilon = 1e-5);
+                    assert_relative_eq!(contact.point.x, expected.point.x, epsilon = 1e-5);
+                    assert_relative_eq!(contact.point.y, expected.point.y, epsilon = 1e-5);
+                    assert_relative_eq!(contact.pen_depth, expected.pen_depth, epsilon = 1e-5);
+                }
+                (contact, _) => panic!("{}: unexpected result {:?}", case.name, contact),
+            }
+        }
+    }
+
+    #[test]
+    fn contact_normal_points_from_a_to_b_for_every_pair() {
+        let ground = Collider::Chain {
+            points: vec![vec2(-2.0, 0.0), vec2(0.0, 0.0), vec2(2.0, 0.0)],
+        };
+        // overlapping placements of every pair of collider types
+        let pairs = [
+            (circle(1.0), vec2(0.0, 0.0), circle(1.0), vec2(1.5, 0.5)),
+            (
+                circle(1.0),
+                vec2(0.0, 0.0),
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(1.5, 0.3),
+            ),
+            (
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(0.0, 0.0),
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(0.4, 1.5),
+            ),
+            (ground.clone(), vec2(0.0, 0.0), circle(0.5), vec2(0.3, 0.2)),
+            (
+                ground.clone(),
+                vec2(0.0, 0.0),
+                aabb(vec2(-0.5, -0.5), vec2(0.5, 0.5)),
+                vec2(0.3, 0.2),
+            ),
+        ];
+
+        for (a, pos_a, b, pos_b) in pairs {
+            // both orders, the normal has to flip with them
+            for (a, pos_a, b, pos_b) in [(&a, pos_a, &b, pos_b), (&b, pos_b, &a, pos_a)] {
+                let body_a = body_at(pos_a);
+                let contact = a
+                    .collides_with(&body_a, &body_at(pos_b), b, 0, 1)
+                    .expect("the pair overlaps");
+                assert_relative_eq!(contact.normal.length(), 1.0, epsilon = 1e-5);
+
+                // moving b along the normal separates the pair, moving it against makes it worse
+                let separated = body_at(pos_b + contact.normal * (contact.pen_depth + 0.01));
+                assert!(
+                    a.collides_with(&body_a, &separated, b, 0, 1).is_none(),
+                    "normal {} doesn't point from a at {pos_a} to b at {pos_b}",
+                    contact.normal
+                );
+                let pushed_in = body_at(pos_b - contact.normal * 0.1);
+                let deeper = a.collides_with(&body_a, &pushed_in, b, 0, 1).unwrap();
+                assert!(deeper.pen_depth > contact.pen_depth);
+            }
+        }
+    }
+
+    #[test]
+    fn circle_fully_inside_box_is_pushed_out_through_nearest_face() {
+        let circle = circle(0.5);
+        let big_box = aabb(vec2(-5.0, -5.0), vec2(5.0, 5.0));
+        // closest to the right face, 1 away from it
+        let circle_body = body_at(vec2(4.0, 1.0));
+        let box_body = body_at(Vec2::ZERO);
+
+        let contact = circle
+            .collides_with(&circle_body, &box_body, &big_box, 0, 1)
+            .unwrap();
+
+        assert_relative_eq!(contact.normal.x, -1.0);
+        assert_relative_eq!(contact.normal.y, 0.0);
+        assert_relative_eq!(contact.point.x, 5.0);
+        assert_relative_eq!(contact.point.y, 1.0);
+        assert_relative_eq!(contact.pen_depth, 1.5);
+
+        // seen from the box the normal flips but the contact stays the same
+        let contact = big_box
+            .collides_with(&box_body, &circle_body, &circle, 1, 0)
+            .unwrap();
+        assert_relative_eq!(contact.normal.x, 1.0);
+        assert_relative_eq!(contact.point.x, 5.0);
+        assert_relative_eq!(contact.pen_depth, 1.5);
+    }
+
+    #[test]
+    fn circle_circle_contact_point_inside_both_at_deep_penetration() {
+        let big = Collider::Circle {
+            offset: Vec2::ZERO,
+            radius: 3.0,
+        };
+        let small = Collider::Circle {
+            offset: Vec2::ZERO,
+            radius: 0.5,
+        };
+        let big_body = body_at(vec2(0.0, 0.0));
+        let small_body = body_at(vec2(1.0, 0.0));
+
+        let contact = big
+            .collides_with(&big_body, &small_body, &small, 0, 1)
+            .unwrap();
+
+        assert_relative_eq!(contact.normal.x, 1.0);
+        assert_relative_eq!(contact.normal.y, 0.0);
+        assert_relative_eq!(contact.pen_depth, 2.5);
+        // the small circle lies completely inside the big one, so the overlap is the small circle
+        assert_relative_eq!(contact.point.x, 1.0);
+        assert_relative_eq!(contact.point.y, 0.0);
+    }
+
+    #[test]
+    fn circle_inside_a_triangle_is_pushed_out_through_the_nearest_edge() {
+        let triangle = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 10.0)];
+        // 1 above the bottom edge, further from the other two
+        let (point, normal, pen_depth) =
+            circle_polygon_contact(vec2(3.0, 1.0), 0.25, &triangle).unwrap();
+        assert_relative_eq!(normal.x, 0.0);
+        assert_relative_eq!(normal.y, -1.0);
+        assert_relative_eq!(point.x, 3.0);
+        assert_relative_eq!(point.y, 0.0);
+        assert_relative_eq!(pen_depth, 1.25);
+
+        // closest to the diagonal edge
+        let (point, normal, pen_depth) =
+            circle_polygon_contact(vec2(4.0, 4.0), 0.25, &triangle).unwrap();
+        let diagonal_normal = vec2(1.0, 1.0).normalize();
+        assert_relative_eq!(normal.x, diagonal_normal.x, epsilon = 1e-6);
+        assert_relative_eq!(normal.y, diagonal_normal.y, epsilon = 1e-6);
+        assert_relative_eq!(point.x, 5.0, epsilon = 1e-5);
+        assert_relative_eq!(point.y, 5.0, epsilon = 1e-5);
+        assert_relative_eq!(pen_depth, 0.25 + 2.0_f32.sqrt(), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn circle_inside_a_box_polygon_matches_the_aabb_interior_case() {
+        let body_pos = vec2(1.0, 1.0);
+        let vertices: Vec<Vec2> = aabb(vec2(-2.0, -1.0), vec2(2.0, 1.0))
+            .to_polygon(4)
+            .into_iter()
+            .map(|vertex| body_pos + vertex)
+            .collect();
+        // 0.2 below the top edge
+        let (point, normal, pen_depth) =
+            circle_polygon_contact(vec2(1.5, 1.8), 0.1, &vertices).unwrap();
+        assert_relative_eq!(normal.x, 0.0);
+        assert_relative_eq!(normal.y, 1.0);
+        assert_relative_eq!(point.x, 1.5);
+        assert_relative_eq!(point.y, 2.0);
+        assert_relative_eq!(pen_depth, 0.3, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn circle_outside_a_polygon_uses_the_closest_corner() {
+        let triangle = [vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(0.0, 10.0)];
+        let (point, normal, pen_depth) =
+            circle_polygon_contact(vec2(-0.3, -0.4), 1.0, &triangle).unwrap();
+        assert_relative_eq!(point.x, 0.0);
+        assert_relative_eq!(point.y, 0.0);
+        assert_relative_eq!(normal.x, -0.6);
+        assert_relative_eq!(normal.y, -0.8);
+        assert_relative_eq!(pen_depth, 0.5);
+
+        assert!(circle_polygon_contact(vec2(-1.0, -1.0), 1.0, &triangle).is_none());
+    }
+
+    #[test]
+    fn support_of_a_circle_lies_on_its_surface_in_the_direction() {
+        let circle = Collider::Circle {
+            offset: vec2(1.0, 0.0),
+            radius: 2.0,
+        };
+        let body = body_at(vec2(0.0, 3.0));
+        let support = circle.support(&body, vec2(0.0, -5.0));
+        assert_relative_eq!(support.x, 1.0);
+        assert_relative_eq!(support.y, 1.0);
+
+        let diagonal = circle.support(&body, vec2(1.0, 1.0));
+        let expected = vec2(1.0, 3.0) + vec2(1.0, 1.0).normalize() * 2.0;
+        assert_relative_eq!(diagonal.x, expected.x);
+        assert_relative_eq!(diagonal.y, expected.y);
+    }
+
+    #[test]
+    fn support_of_a_box_is_the_corner_in_the_direction() {
+        let shape = aabb(vec2(-1.0, -2.0), vec2(1.0, 2.0));
+        let body = body_at(vec2(10.0, 0.0));
+        assert_eq!(shape.support(&body, vec2(1.0, 1.0)), vec2(11.0, 2.0));
+        assert_eq!(shape.support(&body, vec2(-0.1, -3.0)), vec2(9.0, -2.0));
+        assert_eq!(shape.support(&body, vec2(-1.0, 0.5)), vec2(9.0, 2.0));
+    }
+
+    #[test]
+    fn support_of_a_chain_is_its_farthest_point() {
+        let chain = Collider::Chain {
+            points: vec![
+                vec2(0.0, 0.0),
+                vec2(2.0, 1.0),
+                vec2(4.0, 0.0),
+                vec2(2.0, -3.0),
+            ],
+        };
+        let body = body_at(vec2(0.0, 1.0));
+        assert_eq!(chain.support(&body, vec2(0.0, 1.0)), vec2(2.0, 2.0));
+        assert_eq!(chain.support(&body, vec2(1.0, 0.0)), vec2(4.0, 1.0));
+        assert_eq!(chain.support(&body, vec2(0.0, -1.0)), vec2(2.0, -2.0));
+        assert_eq!(chain.support(&body, vec2(-1.0, 0.0)), vec2(0.0, 1.0));
+    }
+
+    #[test]
+    fn sample_surface_walks_around_the_perimeter() {
+        let body = body_at(vec2(10.0, 0.0));
+        let circle = Collider::Circle {
+            offset: vec2(1.0, 0.0),
+            radius: 2.0,
+        };
+        let start = circle.sample_surface(&body, 0.0);
+        assert_relative_eq!(start.x, 13.0);
+        assert_relative_eq!(start.y, 0.0);
+        let quarter = circle.sample_surface(&body, 0.25);
+        assert_relative_eq!(quarter.x, 11.0, epsilon = 1e-5);
+        assert_relative_eq!(quarter.y, 2.0, epsilon = 1e-5);
+
+        // 12 around, so a quarter is 3 along the bottom edge from the min corner
+        let shape = aabb(vec2(-2.0, -1.0), vec2(2.0, 1.0));
+        assert_eq!(shape.sample_surface(&body, 0.0), vec2(8.0, -1.0));
+        assert_eq!(shape.sample_surface(&body, 0.25), vec2(11.0, -1.0));
+        assert_eq!(shape.sample_surface(&body, 1.25), vec2(11.0, -1.0));
+    }
+
+    #[test]
+    fn support_turns_with_the_body() {
+        let mut body = body_at(vec2(10.0, 0.0));
+        body.angle = std::f32::consts::FRAC_PI_2;
+
+        // the box is 4 wide and 2 high, turned it is 2 wide and 4 high
+        let shape = aabb(vec2(-2.0, -1.0), vec2(2.0, 1.0));
+        let corner = shape.support(&body, vec2(1.0, 1.0));
+        assert_relative_eq!(corner.x, 11.0, epsilon = 1e-6);
+        assert_relative_eq!(corner.y, 2.0, epsilon = 1e-6);
+
+        // the offset turns from the right of the position to above it
+        let circle = Collider::Circle {
+            offset: vec2(1.0, 0.0),
+            radius: 0.5,
+        };
+        let top = circle.support(&body, vec2(0.0, 1.0));
+        assert_relative_eq!(top.x, 10.0, epsilon = 1e-6);
+        assert_relative_eq!(top.y, 1.5, epsilon = 1e-6);
+
+        let chain = Collider::Chain {
+            points: vec![vec2(0.0, 0.0), vec2(3.0, 0.0)],
+        };
+        let end = chain.support(&body, vec2(0.0, 1.0));
+        assert_relative_eq!(end.x, 10.0, epsilon = 1e-6);
+        assert_relative_eq!(end.y, 3.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn gjk_distance_matches_the_distance_of_separated_shapes() {
+        let cases = [
+            (circle(1.0), vec2(0.0, 0.0), circle(0.5), vec2(3.0, 4.0)),
+            (
+                circle(1.0),
+                vec2(0.0, 0.0),
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(4.0, 3.0),
+            ),
+            (
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(0.0, 0.0),
+                aabb(vec2(-0.5, -2.0), vec2(0.5, 2.0)),
+                vec2(5.0, 0.5),
+            ),
+            (
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(0.0, 0.0),
+                aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0)),
+                vec2(-4.0, 5.0),
+            ),
+        ];
+        for (a, pos_a, b, pos_b) in cases {
+            let (body_a, body_b) = (body_at(pos_a), body_at(pos_b));
+            let expected = a.distance(&body_a, &b, &body_b);
+            assert_relative_eq!(
+                gjk_distance(&a, &body_a, &b, &body_b),
+                expected,
+                epsilon = 1e-4
+            );
+            assert_relative_eq!(
+                gjk_distance(&b, &body_b, &a, &body_a),
+                expected,
+                epsilon = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn gjk_distance_is_zero_for_touching_and_overlapping_shapes() {
+        let unit_box = aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0));
+        let origin = body_at(Vec2::ZERO);
+
+        let touching = body_at(vec2(2.0, 0.5));
+        assert!(gjk_distance(&unit_box, &origin, &unit_box, &touching) < 1e-4);
+
+        let overlapping = body_at(vec2(1.0, 1.5));
+        assert_eq!(
+            gjk_distance(&unit_box, &origin, &unit_box, &overlapping),
+            0.0
+        );
+        assert_eq!(gjk_distance(&unit_box, &origin, &circle(0.5), &origin), 0.0);
+        assert_eq!(
+            gjk_distance(&circle(1.0), &origin, &circle(1.0), &overlapping),
+            0.0
+        );
+    }
+
+    #[test]
+    fn epa_matches_the_penetration_of_overlapping_boxes() {
+        let a = aabb(vec2(-1.0, -1.0), vec2(1.0, 1.0));
+        let b = aabb(vec2(-0.5, -2.0), vec2(0.5, 2.0));
+        let (body_a, body_b) = (body_at(Vec2::ZERO), body_at(vec2(1.2, 0.3)));
+
+        let contact = a.collides_with(&body_a, &body_b, &b, 0, 1).unwrap();
+        let (normal, depth) = epa_penetration(&a, &body_a, &b, &body_b).unwrap();
+        assert_relative_eq!(depth, contact.pen_depth, epsilon = 1e-4);
+        assert_relative_eq!(depth, 0.3, epsilon = 1e-4);
+        assert_relative_eq!(normal.x, contact.normal.x, epsilon = 1e-4);
+        assert_relative_eq!(normal.y, contact.normal.y, epsilon = 1e-4);
+
+        // boxes on top of each other have to be pushed apart along the shorter side
+        let (normal, depth) = epa_penetration(&a, &body_a, &a, &body_a).unwrap();
+        assert_relative_eq!(depth, 2.0, epsilon = 1e-4);
+        assert_relative_eq!(normal.length(), 1.0, epsilon = 1e-4);
+
+        let far_away = body_at(vec2(5.0, 0.0));
+        assert!(epa_penetration(&a, &body_a, &b, &far_away).is_none());
+    }
+
+    #[test]
+    fn epa_finds_the_penetration_of_overlapping_circles() {
+        let (body_a, body_b) = (body_at(Vec2::ZERO), body_at(vec2(1.2, 1.6)));
+        let (normal, depth) =
+            epa_penetration(&circle(1.5), &body_a, &circle(1.0), &body_b).unwrap();
+        assert_relative_eq!(depth, 0.5, epsilon = 1e-3);
+        assert_relative_eq!(normal.x, 0.6, epsilon = 1e-2);
+        assert_relative_eq!(normal.y, 0.8, epsilon = 1e-2);
+    }
+
+    #[test]
+    fn inertia_of_a_circle_and_a_box() {
+        // I = 1/2 * m * r²
+        assert_relative_eq!(circle(2.0).inertia(3.0), 0.5 * 3.0 * 4.0);
+        // I = 1/12 * m * (w² + h²), independent of where the box is
+        let expected = 3.0 * (2.0 * 2.0 + 4.0 * 4.0) / 12.0;
+        assert_relative_eq!(
+            aabb(vec2(-1.0, -2.0), vec2(1.0, 2.0)).inertia(3.0),
+            expected
+        );
+        assert_relative_eq!(aabb(vec2(5.0, 0.0), vec2(7.0, 4.0)).inertia(3.0), expected);
+    }
+}
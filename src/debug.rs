@@ -0,0 +1,343 @@
+use crate::Camera;
+use crate::Contact;
+use crate::Object;
+use crate::World;
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+// a contact marker that is kept around for a few frames after the contact happened
+struct PersistentContact {
+    point: Vec2,
+    normal: Vec2,
+    age: u32, // in frames
+}
+
+pub struct DebugRenderer {
+    // draw the friction cone (±mu * p_n) and the applied tangent impulse at every contact
+    pub draw_friction_cones: bool,
+    // draw the world aabb of every collider on top of its shape
+    pub draw_bounds: bool,
+    // label every contact with the names of its two objects, see `draw_contact_sides`
+    pub draw_contact_sides: bool,
+    // draw the linear and angular velocity of every body, see `draw_velocities`
+    pub draw_velocities: bool,
+    // world units the velocity line is long per unit of speed
+    pub velocity_scale: f32,
+    // draw a cross at the center of mass of every body, see `draw_centers_of_mass`
+    pub draw_centers_of_mass: bool,
+    // keep contact markers around for `contact_ttl` frames so that short-lived contacts are visible
+    pub persist_contacts: bool,
+    pub contact_ttl: u32,
+    // the length of the drawn contact normals in world units, so it doesn't change with the zoom
+    pub contact_normal_length: f32,
+    // the oldest markers are dropped once the buffer is full
+    pub max_persistent_contacts: usize,
+    persistent_contacts: VecDeque<PersistentContact>,
+}
+
+impl Default for DebugRenderer {
+    fn default() -> Self {
+        Self {
+            draw_friction_cones: false,
+            draw_bounds: false,
+            draw_contact_sides: false,
+            draw_velocities: false,
+            velocity_scale: 0.25,
+            draw_centers_of_mass: false,
+            persist_contacts: false,
+            contact_ttl: 60,
+            contact_normal_length: 0.5,
+            max_persistent_contacts: 256,
+            persistent_contacts: VecDeque::new(),
+        }
+    }
+}
+
+impl DebugRenderer {
+    /// Draws the contacts of the last step. Has to be called once per frame for the
+    /// persistent markers to age correctly.
+    pub fn draw_contacts(&mut self, camera: &Camera, contacts: &[Contact]) {
+        if self.persist_contacts {
+            self.update_persistent_contacts(contacts);
+            for marker in self.persistent_contacts.iter() {
+                // fade out linearly over the lifetime of the marker
+                let alpha = 1.0 - marker.age as f32 / self.contact_ttl as f32;
+                draw_contact_marker(
+                    camera,
+                    marker.point,
+                    marker.normal * self.contact_normal_length,
+                    alpha,
+                );
+            }
+        } else {
+            self.persistent_contacts.clear();
+        }
+
+        for contact in contacts {
+            draw_contact_marker(
+                camera,
+                contact.point,
+                contact.normal * self.contact_normal_length,
+                1.0,
+            );
+
+            if self.draw_friction_cones {
+                draw_friction_cone(camera, contact);
+            }
+        }
+    }
+
+    pub fn draw_object_bounds(&self, camera: &Camera, objects: &[Object]) {
+        if !self.draw_bounds {
+            return;
+        }
+        for object in objects.iter().filter(|object| object.enabled) {
+            let (Some(collider), Some(body)) = (&object.collider, &object.body) else {
+                continue;
+            };
+            let (min, max) = collider.world_bounds(body.position);
+            let screen_a = camera.world_to_screen(min);
+            let screen_b = camera.world_to_screen(max);
+            let screen_top_left = screen_a.min(screen_b);
+            let screen_size = (screen_b - screen_a).abs();
+            draw_rectangle_lines(
+                screen_top_left.x,
+                screen_top_left.y,
+                screen_size.x,
+                screen_size.y,
+                1.0,
+                GRAY,
+            );
+        }
+    }
+
+    /// Draws a line from the center of every body along its velocity and an arc around the center
+    /// for its angular velocity. The arc starts at the current angle of the body and sweeps in the
+    /// direction of the spin, a full turn stands for one turn per second or more.
+    pub fn draw_velocities(&self, camera: &Camera, objects: &[Object]) {
+        if !self.draw_velocities {
+            return;
+        }
+        for object in objects.iter().filter(|object| object.enabled) {
+            let Some(body) = &object.body else {
+                continue;
+            };
+            if body.is_static {
+                continue;
+            }
+            let center = camera.world_to_screen(body.position);
+            let end = center + camera.world_to_screen_vec(body.vel * self.velocity_scale);
+            draw_line(center.x, center.y, end.x, end.y, 1.0, BLUE);
+
+            let sweep = body
+                .angular_vel
+                .clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+            if sweep == 0.0 {
+                continue;
+            }
+            // draw_arc sweeps clockwise on the screen from `rotation`, so a counter-clockwise
+            // spin starts at the far end instead
+            let start = camera.world_angle_to_screen(body.angle);
+            let screen_sweep = camera.world_angle_to_screen(sweep);
+            let rotation = start + screen_sweep.min(0.0);
+            let radius = 12.0; // in pixels
+            draw_arc(
+                center.x,
+                center.y,
+                32,
+                radius,
+                rotation.to_degrees(),
+                1.0,
+                screen_sweep.abs().to_degrees(),
+                PURPLE,
+            );
+            // mark where the arc ends so the direction of the spin is visible
+            let tip = center + Vec2::from_angle(start + screen_sweep) * radius;
+            draw_circle(tip.x, tip.y, 2.5, PURPLE);
+        }
+    }
+
+    /// Draws a cross at the center of mass of every body. Bodies whose center of mass isn't at
+    /// their position, like ones with an offset collider, also get a line from the position
+    /// to the cross.
+    pub fn draw_centers_of_mass(&self, camera: &Camera, objects: &[Object]) {
+        if !self.draw_centers_of_mass {
+            return;
+        }
+        let size = 5.0; // in pixels
+        for object in objects.iter().filter(|object| object.enabled) {
+            let Some(body) = &object.body else {
+                continue;
+            };
+            let center = camera.world_to_screen(body.center_of_mass());
+            draw_line(
+                center.x - size,
+                center.y - size,
+                center.x + size,
+                center.y + size,
+                1.5,
+                MAGENTA,
+            );
+            draw_line(
+                center.x - size,
+                center.y + size,
+                center.x + size,
+                center.y - size,
+                1.5,
+                MAGENTA,
+            );
+
+            let position = camera.world_to_screen(body.position);
+            if position.distance(center) > 1.0 {
+                draw_line(position.x, position.y, center.x, center.y, 1.0, MAGENTA);
+            }
+        }
+    }
+
+    /// Shows which body of a contact is A and which is B. The normal always points from A
+    /// toward B, so it is drawn starting in the color of A and ending in the color of B,
+    /// with a label "A -> B" made of the object names next to the contact point.
+    pub fn draw_contact_sides(&self, camera: &Camera, contacts: &[Contact], objects: &[Object]) {
+        if !self.draw_contact_sides {
+            return;
+        }
+        for contact in contacts {
+            let (Some(a), Some(b)) = (
+                objects.get(contact.body_a_index),
+                objects.get(contact.body_b_index),
+            ) else {
+                continue;
+            };
+
+            let length = 20.0; // in pixels
+            let screen_point = camera.world_to_screen(contact.point);
+            let normal = camera
+                .world_to_screen_vec(contact.normal)
+                .normalize_or_zero();
+            let middle = screen_point + normal * length * 0.5;
+            let end = screen_point + normal * length;
+            draw_line(
+                screen_point.x,
+                screen_point.y,
+                middle.x,
+                middle.y,
+                2.0,
+                a.color,
+            );
+            draw_line(middle.x, middle.y, end.x, end.y, 2.0, b.color);
+
+            let label = format!("{} -> {}", a.name, b.name);
+            draw_text(&label, end.x + 4.0, end.y, 16.0, BLACK);
+        }
+    }
+
+    /// draws every joint as a line between the bodies it connects
+    pub fn draw_joints(&self, camera: &Camera, world: &World) {
+        for joint in world.joints() {
+            let position = |handle| Some(world.get(handle)?.body.as_ref()?.position);
+            let (Some(a), Some(b)) = (position(joint.a), position(joint.b)) else {
+                continue;
+            };
+            let screen_a = camera.world_to_screen(a);
+            let screen_b = camera.world_to_screen(b);
+            draw_line(
+                screen_a.x, screen_a.y, screen_b.x, screen_b.y, 1.0, DARKGRAY,
+            );
+        }
+    }
+
+    fn update_persistent_contacts(&mut self, contacts: &[Contact]) {
+        for marker in self.persistent_contacts.iter_mut() {
+            marker.age += 1;
+        }
+        self.persistent_contacts
+            .retain(|marker| marker.age < self.contact_ttl);
+
+        for contact in contacts {
+            if self.max_persistent_contacts == 0 {
+                break;
+            }
+            if self.persistent_contacts.len() >= self.max_persistent_contacts {
+                self.persistent_contacts.pop_front();
+            }
+            self.persistent_contacts.push_back(PersistentContact {
+                point: contact.point,
+                normal: contact.normal,
+                age: 0,
+            });
+        }
+    }
+}
+
+// `normal` is in world units and is drawn as it is, scaled by the zoom like everything else
+fn draw_contact_marker(camera: &Camera, point: Vec2, normal: Vec2, alpha: f32) {
+    let screen_point = camera.world_to_screen(point);
+    draw_circle_lines(
+        screen_point.x,
+        screen_point.y,
+        1.0,
+        1.0,
+        BLACK.with_alpha(alpha),
+    );
+    let normal_end = screen_point + camera.world_to_screen_vec(normal);
+
+    draw_circle_lines(
+        screen_point.x,
+        screen_point.y,
+        2.0,
+        1.0,
+        BLACK.with_alpha(alpha),
+    );
+
+    draw_line(
+        screen_point.x,
+        screen_point.y,
+        normal_end.x,
+        normal_end.y,
+        1.0,
+        RED.with_alpha(alpha),
+    );
+}
+
+/// Draws the edges of the friction cone and the direction of the impulse that was actually applied.
+/// The impulse is drawn green if the contact is sticking and orange if it is sliding
+/// (the tangent impulse was clamped to the edge of the cone).
+fn draw_friction_cone(camera: &Camera, contact: &Contact) {
+    // in pixels, the cone only shows directions so the magnitude of the impulse doesn't matter
+    let length = 20.0;
+
+    let screen_point = camera.world_to_screen(contact.point);
+    let tangent = contact.normal.perp();
+
+    let left_edge = (contact.normal + contact.mu * tangent).normalize();
+    let right_edge = (contact.normal - contact.mu * tangent).normalize();
+    for edge in [left_edge, right_edge] {
+        let edge_end = screen_point + camera.world_to_screen_vec(edge).normalize() * length;
+        draw_line(
+            screen_point.x,
+            screen_point.y,
+            edge_end.x,
+            edge_end.y,
+            1.0,
+            BLUE,
+        );
+    }
+
+    let impulse = (contact.normal_impulse * contact.normal + contact.tangent_impulse * tangent)
+        .normalize_or_zero();
+    let impulse_end =
+        screen_point + camera.world_to_screen_vec(impulse).normalize_or_zero() * length;
+
+    let friction_limit = contact.mu * contact.normal_impulse;
+    let is_sliding = friction_limit > 0.0 && contact.tangent_impulse.abs() >= friction_limit;
+    let color = if is_sliding { ORANGE } else { GREEN };
+
+    draw_line(
+        screen_point.x,
+        screen_point.y,
+        impulse_end.x,
+        impulse_end.y,
+        2.0,
+        color,
+    );
+}
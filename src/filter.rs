@@ -0,0 +1,120 @@
+/// A whole name, or the start of a name if the pattern ends with `*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NamePattern {
+    Exact(String),
+    Prefix(String),
+}
+
+impl NamePattern {
+    fn parse(pattern: &str) -> Self {
+        match pattern.strip_suffix('*') {
+            Some(prefix) => NamePattern::Prefix(prefix.to_string()),
+            None => NamePattern::Exact(pattern.to_string()),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Exact(exact) => name == exact,
+            NamePattern::Prefix(prefix) => name.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+// every rule takes two bits of a mask, one for each of its patterns
+const FIRST_PATTERN_BITS: u64 = 0x5555_5555_5555_5555;
+
+/// Keeps objects from colliding based on their names, like "bullets don't hit the player".
+/// The patterns are parsed once when a rule is added. The collision detection matches every
+/// object against them once per pass and only compares bit masks for the pairs.
+#[derive(Debug, Clone, Default)]
+pub struct NameFilter {
+    rules: Vec<(NamePattern, NamePattern)>,
+}
+
+impl NameFilter {
+    pub const MAX_RULES: usize = 32;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Objects whose names match `pattern_a` no longer collide with objects whose names match
+    /// `pattern_b`, e.g. `ignore_pair("bullet*", "player")`. A pattern ending with `*` matches
+    /// every name starting with the rest of it. Returns false if there are `MAX_RULES` already.
+    pub fn ignore_pair(&mut self, pattern_a: &str, pattern_b: &str) -> bool {
+        if self.rules.len() == Self::MAX_RULES {
+            return false;
+        }
+        self.rules
+            .push((NamePattern::parse(pattern_a), NamePattern::parse(pattern_b)));
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// true if objects with these names are allowed to collide
+    pub fn allows(&self, name_a: &str, name_b: &str) -> bool {
+        Self::masks_allow(self.mask(name_a), self.mask(name_b))
+    }
+
+    /// Bit 2i is set if the name matches the first pattern of rule i, bit 2i + 1 if it matches
+    /// the second one.
+    pub(crate) fn mask(&self, name: &str) -> u64 {
+        let mut mask = 0;
+        for (i, (pattern_a, pattern_b)) in self.rules.iter().enumerate() {
+            if pattern_a.matches(name) {
+                mask |= 1 << (2 * i);
+            }
+            if pattern_b.matches(name) {
+                mask |= 1 << (2 * i + 1);
+            }
+        }
+        mask
+    }
+
+    pub(crate) fn masks_allow(mask_a: u64, mask_b: u64) -> bool {
+        // a rule forbids the pair if one name matches its first pattern and the other its second
+        let a_first = mask_a & FIRST_PATTERN_BITS;
+        let a_second = (mask_a >> 1) & FIRST_PATTERN_BITS;
+        let b_first = mask_b & FIRST_PATTERN_BITS;
+        let b_second = (mask_b >> 1) & FIRST_PATTERN_BITS;
+        a_first & b_second == 0 && a_second & b_first == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_apply_in_both_directions_and_match_prefixes() {
+        let mut filter = NameFilter::new();
+        filter.ignore_pair("bullet*", "player");
+
+        assert!(!filter.allows("bullet_1", "player"));
+        assert!(!filter.allows("player", "bullet"));
+        assert!(filter.allows("bullet_1", "player_2"));
+        assert!(filter.allows("bullet_1", "bullet_2"));
+        assert!(filter.allows("player", "floor"));
+    }
+
+    #[test]
+    fn only_max_rules_can_be_added() {
+        let mut filter = NameFilter::new();
+        for i in 0..NameFilter::MAX_RULES {
+            assert!(filter.ignore_pair(&format!("a{i}"), &format!("b{i}")));
+        }
+        assert!(!filter.ignore_pair("a", "b"));
+
+        let last = NameFilter::MAX_RULES - 1;
+        assert!(!filter.allows(&format!("b{last}"), &format!("a{last}")));
+        assert!(filter.allows(&format!("a{last}"), "b0"));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::rigid_body::*;
+use crate::world::ObjectHandle;
+use macroquad::prelude::*;
+
+/// Pulls every dynamic body towards the center of mass of the `attractor` object with a force that
+/// falls off with the square of the distance, like the gravity of a planet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointGravity {
+    pub attractor: ObjectHandle,
+    // the acceleration at a distance of 1, i.e. G * M of the attractor
+    pub strength: f32,
+    // closer bodies are pulled as if they were this far away, so the force can't blow up
+    pub min_distance: f32,
+}
+
+impl PointGravity {
+    pub fn new(attractor: ObjectHandle, strength: f32) -> Self {
+        Self {
+            attractor,
+            strength,
+            min_distance: 0.1,
+        }
+    }
+
+    /// The force on `body` from an attractor at `center`. It pulls on the center of mass and is
+    /// scaled by the mass of the body, so light and heavy bodies fall the same way.
+    /// Static and infinitely heavy bodies get none.
+    pub fn force_on(&self, center: Vec2, body: &RigidBody2D) -> Vec2 {
+        let Some(mass) = body.mass() else {
+            return Vec2::ZERO;
+        };
+        let delta = center - body.center_of_mass();
+        let distance = delta.length().max(self.min_distance);
+        let Some(direction) = delta.try_normalize() else {
+            return Vec2::ZERO;
+        };
+        direction * mass * self.strength / (distance * distance)
+    }
+
+    /// The speed a body at `distance` from the attractor needs for a circular orbit,
+    /// perpendicular to the direction of the attractor.
+    pub fn orbital_speed(&self, distance: f32) -> f32 {
+        (self.strength / distance.max(self.min_distance)).sqrt()
+    }
+}
@@ -0,0 +1,97 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+/// The semantic actions the user can trigger with the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    FitToContents,
+    ToggleFrictionCones,
+    TogglePersistentContacts,
+    ToggleBounds,
+    ToggleContactSides,
+    ToggleVelocities,
+    ToggleCentersOfMass,
+    SwitchScene,
+}
+
+/// Maps actions to the keys that trigger them. An action can have several keys,
+/// but every key triggers at most one action.
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let mut input_map = Self::empty();
+        input_map.bind(Action::PanLeft, KeyCode::A);
+        input_map.bind(Action::PanRight, KeyCode::D);
+        input_map.bind(Action::PanUp, KeyCode::W);
+        input_map.bind(Action::PanDown, KeyCode::S);
+        input_map.bind(Action::ZoomIn, KeyCode::Z);
+        input_map.bind(Action::ZoomOut, KeyCode::X);
+        input_map.bind(Action::FitToContents, KeyCode::R);
+        input_map.bind(Action::ToggleFrictionCones, KeyCode::F);
+        input_map.bind(Action::TogglePersistentContacts, KeyCode::C);
+        input_map.bind(Action::ToggleBounds, KeyCode::B);
+        input_map.bind(Action::ToggleContactSides, KeyCode::N);
+        input_map.bind(Action::ToggleVelocities, KeyCode::V);
+        input_map.bind(Action::ToggleCentersOfMass, KeyCode::M);
+        input_map.bind(Action::SwitchScene, KeyCode::O);
+        input_map
+    }
+}
+
+impl InputMap {
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Binds `key` to `action`. If the key was bound to another action it is taken away from it
+    /// and that action is returned.
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> Option<Action> {
+        let previous = self.action_for(key);
+        if let Some(previous) = previous {
+            self.unbind(previous, key);
+        }
+        self.bindings.entry(action).or_default().push(key);
+        previous.filter(|previous| *previous != action)
+    }
+
+    pub fn unbind(&mut self, action: Action, key: KeyCode) {
+        if let Some(keys) = self.bindings.get_mut(&action) {
+            keys.retain(|bound| *bound != key);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings
+            .get(&action)
+            .map_or(&[], |keys| keys.as_slice())
+    }
+
+    /// Returns the action the key is bound to, if any.
+    pub fn action_for(&self, key: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// true while any key of the action is held down
+    pub fn is_down(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|key| is_key_down(*key))
+    }
+
+    /// true in the frame any key of the action was pressed
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.keys(action).iter().any(|key| is_key_pressed(*key))
+    }
+}
@@ -0,0 +1,81 @@
+use crate::Contact;
+use crate::object::*;
+use std::collections::HashMap;
+
+/// A group of bodies that are connected by contacts or joints, directly or through other bodies
+/// of the group. Nothing that happens in one island affects another one during a step, so each
+/// island can be solved on its own. Static bodies don't connect the bodies touching them.
+#[derive(Debug, Default)]
+pub(crate) struct Island {
+    // indices into the objects of the world
+    pub bodies: Vec<usize>,
+    // indices into the contacts and joints that were passed to `build_islands`
+    pub contacts: Vec<usize>,
+    pub joints: Vec<usize>,
+}
+
+/// Groups the enabled dynamic bodies into islands. `joints` holds the object indices of the two
+/// bodies of every joint, None for joints whose bodies don't exist anymore. Every dynamic body
+/// ends up in exactly one island, bodies without any contact or joint get one of their own.
+pub(crate) fn build_islands(
+    objects: &[Object],
+    contacts: &[Contact],
+    joints: &[Option<(usize, usize)>],
+) -> Vec<Island> {
+    let is_dynamic = |index: usize| {
+        let object = &objects[index];
+        object.enabled && object.body.as_ref().is_some_and(|body| !body.is_static)
+    };
+
+    let mut parents: Vec<usize> = (0..objects.len()).collect();
+    let pairs = contacts
+        .iter()
+        .map(|contact| (contact.body_a_index, contact.body_b_index))
+        .chain(joints.iter().flatten().copied());
+    for (a, b) in pairs {
+        if is_dynamic(a) && is_dynamic(b) {
+            let (root_a, root_b) = (find_root(&mut parents, a), find_root(&mut parents, b));
+            parents[root_a] = root_b;
+        }
+    }
+
+    let mut islands: Vec<Island> = vec![];
+    let mut island_of_root = HashMap::new();
+    let mut island_of: Vec<Option<usize>> = vec![None; objects.len()];
+    for (index, island_index) in island_of.iter_mut().enumerate() {
+        if !is_dynamic(index) {
+            continue;
+        }
+        let root = find_root(&mut parents, index);
+        let island = *island_of_root.entry(root).or_insert_with(|| {
+            islands.push(Island::default());
+            islands.len() - 1
+        });
+        islands[island].bodies.push(index);
+        *island_index = Some(island);
+    }
+
+    // a constraint belongs to the island of whichever of its bodies is dynamic,
+    // constraints between two immovable bodies don't need solving
+    let island_of_pair = |(a, b): (usize, usize)| island_of[a].or(island_of[b]);
+    for (i, contact) in contacts.iter().enumerate() {
+        if let Some(island) = island_of_pair((contact.body_a_index, contact.body_b_index)) {
+            islands[island].contacts.push(i);
+        }
+    }
+    for (i, &pair) in joints.iter().enumerate() {
+        if let Some(island) = pair.and_then(island_of_pair) {
+            islands[island].joints.push(i);
+        }
+    }
+    islands
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        // path halving keeps the trees flat
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
@@ -0,0 +1,53 @@
+use crate::rigid_body::*;
+use crate::world::ObjectHandle;
+use macroquad::prelude::*;
+
+// how much of the length error a rigid joint removes per step
+const JOINT_BIAS_FACTOR: f32 = 0.2;
+
+/// Keeps the centers of two bodies at a fixed distance from each other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceJoint {
+    pub a: ObjectHandle,
+    pub b: ObjectHandle,
+    pub rest_length: f32,
+    // between 0 and 1, 1 is a rigid rod and lower values make the joint springy
+    pub stiffness: f32,
+}
+
+impl DistanceJoint {
+    pub fn new(a: ObjectHandle, b: ObjectHandle, rest_length: f32, stiffness: f32) -> Self {
+        Self {
+            a,
+            b,
+            rest_length,
+            stiffness: stiffness.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Applies the impulse along the joint that moves the bodies towards the rest length.
+    pub(crate) fn solve(&self, body_a: &mut RigidBody2D, body_b: &mut RigidBody2D, dt: f32) {
+        let k = body_a.inverse_mass + body_b.inverse_mass;
+        if k == 0.0 {
+            return;
+        }
+        let delta = body_b.position - body_a.position;
+        let length = delta.length();
+        let Some(direction) = delta.try_normalize() else {
+            return;
+        };
+
+        // positive if the joint is stretched
+        let error = length - self.rest_length;
+        let relative_vel = (body_b.vel - body_a.vel).dot(direction);
+        let bias_vel = (JOINT_BIAS_FACTOR / dt) * error;
+
+        let impulse = -self.stiffness * (relative_vel + bias_vel) / k * direction;
+        if !body_a.is_static {
+            body_a.apply_impulse(-impulse);
+        }
+        if !body_b.is_static {
+            body_b.apply_impulse(impulse);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+pub mod batch;
+pub mod camera;
+pub mod collider;
+pub mod debug;
+pub mod filter;
+pub mod force;
+pub mod input;
+mod island;
+pub mod joint;
+pub mod object;
+pub mod rigid_body;
+pub mod world;
+
+pub use batch::*;
+pub use camera::{Camera, CoordinateSystem};
+pub use collider::*;
+pub use debug::*;
+pub use filter::*;
+pub use force::*;
+pub use input::*;
+pub use joint::*;
+pub use object::*;
+pub use rigid_body::*;
+pub use world::*;
+
+use macroquad::prelude::*;
+
+/// The up direction of the world. The default gravities point against it and
+/// `CoordinateSystem::YUp` draws it towards the top of the screen.
+pub const UP: Vec2 = Vec2::Y;
+
+#[derive(Debug, Default, Clone)]
+pub struct Contact {
+    pub point: Vec2, // point of contact
+    // unit vector pointing from body_a toward body_b, for every pair of collider types.
+    // moving body_b by `normal * pen_depth` separates the two
+    pub normal: Vec2,
+
+    pub pen_depth: f32, // how deep body_a is inside of body_b
+
+    pub body_a_index: usize,
+    pub body_b_index: usize,
+
+    // filled in by the solver once the contact has been resolved
+    pub approach_speed: f32, // the relative velocity along the normal before resolving
+    // the impulses summed over all solver iterations
+    pub normal_impulse: f32,
+    pub tangent_impulse: f32,
+    pub mu: f32, // the combined coefficient of friction that was used
+}
@@ -1,60 +1,105 @@
 use crate::Camera;
+use crate::batch::LineBatch;
 use crate::collider::*;
 use crate::rigid_body::*;
 use macroquad::prelude::*;
 
+// number of sides of the polygon that circles are drawn as
+const CIRCLE_SIDES: usize = 24;
+
+#[derive(Clone)]
 pub struct Object {
     pub body: Option<RigidBody2D>,
     pub collider: Option<Collider>,
     pub color: Color,
     pub name: String,
+    // sensors detect overlaps but are never pushed apart from other objects
+    pub is_sensor: bool,
+    // disabled objects stay in the world but are skipped by the simulation, the queries
+    // and the drawing, which is cheaper than removing and adding them again
+    pub enabled: bool,
 }
 
 impl Object {
+    /// Returns the position and angle of the body blended between the previous and the current step.
+    /// `alpha` = 0 is the previous state and `alpha` = 1 the current one.
+    pub fn interpolated_transform(&self, alpha: f32) -> Option<(Vec2, f32)> {
+        let body = self.body.as_ref()?;
+        let position = body.prev_position.lerp(body.position, alpha);
+        let angle = body.prev_angle + (body.angle - body.prev_angle) * alpha;
+        Some((position, angle))
+    }
+
     pub fn draw(&self, camera: &Camera) {
         let Some(body) = &self.body else {
             return;
         };
+        self.draw_at(camera, body.position);
+    }
+
+    /// draws the object at its interpolated position, see `interpolated_transform`
+    pub fn draw_interpolated(&self, camera: &Camera, alpha: f32) {
+        let Some((position, _)) = self.interpolated_transform(alpha) else {
+            return;
+        };
+        self.draw_at(camera, position);
+    }
+
+    fn draw_at(&self, camera: &Camera, position: Vec2) {
+        let mut batch = LineBatch::new();
+        self.add_outline(camera, position, &mut batch);
+        batch.flush();
+    }
+
+    /// Adds the outline of the collider at `position` to `batch` without drawing it yet.
+    pub fn add_outline(&self, camera: &Camera, position: Vec2, batch: &mut LineBatch) {
         let Some(collider) = &self.collider else {
             return;
         };
 
         match collider {
             Collider::Circle { offset, radius } => {
-                let world_pos = body.position + *offset;
+                let world_pos = position + *offset;
                 let screen_pos = camera.world_to_screen(world_pos);
                 let screen_radius = *radius * camera.zoom.x; // assume uniform zoom
-                draw_circle_lines(screen_pos.x, screen_pos.y, screen_radius, 2.0, self.color);
+                batch.add_circle(screen_pos, screen_radius, CIRCLE_SIDES, 2.0, self.color);
             }
 
             Collider::AABB { min, max } => {
-                let world_min = body.position + *min;
-                let world_max = body.position + *max;
-
-                let top_left = vec2(world_min.x, world_max.y); // because Y+ is up
-                let size = world_max - world_min;
-
-                let screen_top_left = camera.world_to_screen(top_left);
-                let screen_size = size * camera.zoom;
-
-                draw_rectangle_lines(
-                    screen_top_left.x,
-                    screen_top_left.y,
-                    screen_size.x,
-                    -screen_size.y, // flip Y for screen space
-                    2.0,
-                    self.color,
-                );
+                let screen_a = camera.world_to_screen(position + *min);
+                let screen_b = camera.world_to_screen(position + *max);
+
+                // which corner ends up top left depends on the coordinate system
+                let screen_top_left = screen_a.min(screen_b);
+                let screen_size = (screen_b - screen_a).abs();
+
+                batch.add_rectangle(screen_top_left, screen_size, 2.0, self.color);
+            }
+
+            Collider::Chain { points } => {
+                for pair in points.windows(2) {
+                    let start = camera.world_to_screen(position + pair[0]);
+                    let end = camera.world_to_screen(position + pair[1]);
+                    batch.add_line(start, end, 2.0, self.color);
+                }
             }
         }
     }
 }
 
+#[derive(Clone)]
 pub struct ObjectBuilder {
     pub body: Option<RigidBody2D>,
     pub collider: Option<Collider>,
     pub color: Option<Color>,
     pub name: Option<String>,
+    pub is_sensor: bool,
+}
+
+impl Default for ObjectBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ObjectBuilder {
@@ -64,6 +109,7 @@ impl ObjectBuilder {
             collider: None,
             color: None,
             name: None,
+            is_sensor: false,
         }
     }
 
@@ -87,14 +133,21 @@ impl ObjectBuilder {
         self
     }
 
+    pub fn make_sensor(mut self) -> Self {
+        self.is_sensor = true;
+        self
+    }
+
     pub fn build(self) -> Object {
         let color = self.color.expect("Expected the user to pass a color");
         let name = self.name.unwrap_or_else(|| "some_object".to_string());
         Object {
             body: self.body,
             collider: self.collider,
-            color: color,
-            name: name,
+            color,
+            name,
+            is_sensor: self.is_sensor,
+            enabled: true,
         }
     }
 }
@@ -1,10 +1,89 @@
 use crate::Collider;
 use macroquad::prelude::*;
+use std::fmt;
 
+// `set_mass` never goes below this, a mass of 0 would mean an infinite inverse mass
+const MIN_MASS: f32 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    // the inverse mass has to be finite and not negative
+    InvalidInverseMass(f32),
+    // a circle needs a positive radius
+    InvalidRadius(f32),
+    // the min corner of an aabb lies above or to the right of its max corner
+    InvertedAABB { min: Vec2, max: Vec2 },
+    // a chain needs at least two points to form a segment
+    InvalidChain(usize),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidInverseMass(inverse_mass) => write!(
+                f,
+                "inverse mass must be finite and >= 0, but was {inverse_mass}"
+            ),
+            BuildError::InvalidRadius(radius) => {
+                write!(f, "circle radius must be > 0, but was {radius}")
+            }
+            BuildError::InvertedAABB { min, max } => write!(
+                f,
+                "aabb min {min} must not be greater than max {max} on any axis"
+            ),
+            BuildError::InvalidChain(point_count) => {
+                write!(f, "chain needs at least 2 points, but had {point_count}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// The surface properties of a body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsMaterial {
+    pub restitution: f32,
+    pub mu: f32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self {
+            restitution: 0.5,
+            mu: 0.3,
+        }
+    }
+}
+
+/// Friction that depends on the direction of sliding, like on a brush or a grooved surface.
+/// In between the two directions the coefficient is interpolated along an ellipse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnisotropicFriction {
+    // in body coordinates, rotates with the body
+    pub direction: Vec2,
+    pub mu_along: f32,
+    pub mu_across: f32,
+}
+
+/// Restitution that only applies to contacts whose normal lines up with an axis, like a
+/// trampoline that bounces things up but not sideways. Other contacts use `restitution`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalRestitution {
+    // in body coordinates, rotates with the body. Contacts on both sides of the axis count
+    pub axis: Vec2,
+    pub restitution: f32,
+    // the largest angle between the contact normal and the axis, in radians
+    pub max_angle: f32,
+}
+
+#[derive(Clone)]
 pub struct RigidBody2DBuilder {
     position: Vec2,
     angle: f32,
     angular_vel: f32,
+    // converted to the angular velocity once the inertia is known in `build`
+    angular_momentum: Option<f32>,
     vel: Vec2,
     // accumulated force
     accum_force: Vec2,
@@ -12,9 +91,22 @@ pub struct RigidBody2DBuilder {
     inverse_mass: f32,
     inverse_inertia: f32,
     is_static: bool,
+    // None leaves it to the shape, see `with_fixed_rotation`
+    fixed_rotation: Option<bool>,
+    com_at_origin: bool,
     shape: Option<Collider>,
     restitution: f32,
     mu: f32,
+    gravity_scale: f32,
+    gravity_override: Option<Vec2>,
+    anisotropic_friction: Option<AnisotropicFriction>,
+    directional_restitution: Option<DirectionalRestitution>,
+}
+
+impl Default for RigidBody2DBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RigidBody2DBuilder {
@@ -24,14 +116,21 @@ impl RigidBody2DBuilder {
             angle: 0.0,
             vel: Vec2::ZERO,
             angular_vel: 0.0,
+            angular_momentum: None,
             accum_force: Vec2::ZERO,
             accum_torque: 0.0,
             inverse_mass: 1.0,
             inverse_inertia: 1.0,
             is_static: false,
+            fixed_rotation: None,
+            com_at_origin: false,
             shape: None,
             restitution: 0.5,
             mu: 0.3,
+            gravity_scale: 1.0,
+            gravity_override: None,
+            anisotropic_friction: None,
+            directional_restitution: None,
         }
     }
 
@@ -60,70 +159,224 @@ impl RigidBody2DBuilder {
         self
     }
 
+    /// Sets the spin as angular momentum (I * angular velocity) instead of angular velocity, so
+    /// heavier bodies spin slower. Takes precedence over `with_angular_vel`.
+    pub fn with_angular_momentum(mut self, angular_momentum: f32) -> Self {
+        self.angular_momentum = Some(angular_momentum);
+        self
+    }
+
     pub fn with_vel(mut self, vel: Vec2) -> Self {
         self.vel = vel;
         self
     }
 
+    /// shorthand for `with_vel` and `with_angular_vel`
+    pub fn with_motion(self, vel: Vec2, angular_vel: f32) -> Self {
+        self.with_vel(vel).with_angular_vel(angular_vel)
+    }
+
+    /// Sets the whole initial state of the body at once.
+    pub fn with_state(self, position: Vec2, angle: f32, vel: Vec2, angular_vel: f32) -> Self {
+        self.with_position(position)
+            .with_angle(angle)
+            .with_motion(vel, angular_vel)
+    }
+
     pub fn make_static(mut self) -> Self {
         self.is_static = true;
         self
     }
 
+    /// A body with fixed rotation still collides and translates but never rotates,
+    /// as if it had an infinite moment of inertia. By default only AABBs have fixed rotation,
+    /// since their collider can't turn with the body.
+    pub fn with_fixed_rotation(mut self, fixed_rotation: bool) -> Self {
+        self.fixed_rotation = Some(fixed_rotation);
+        self
+    }
+
+    /// By default the center of mass is the center of the shape, so an offset collider turns
+    /// around its own center. With this the body turns around `position` instead, like a turret
+    /// barrel that is attached to its base, and the inertia grows accordingly.
+    pub fn with_com_at_origin(mut self, com_at_origin: bool) -> Self {
+        self.com_at_origin = com_at_origin;
+        self
+    }
+
+    /// clamped to [0, 1], a restitution above 1 would make the body gain energy with every bounce
     pub fn with_restitution(mut self, restitution: f32) -> Self {
-        self.restitution = restitution;
+        self.restitution = restitution.clamp(0.0, 1.0);
         self
     }
 
+    pub fn with_material(self, material: PhysicsMaterial) -> Self {
+        self.with_restitution(material.restitution)
+            .with_mu(material.mu)
+    }
+
+    /// clamped to >= 0, negative friction would accelerate sliding bodies
     pub fn with_mu(mut self, mu: f32) -> Self {
-        self.mu = mu;
+        self.mu = mu.max(0.0);
+        self
+    }
+
+    /// how strongly the body is affected by the world gravity, 0 makes it float
+    pub fn with_gravity_scale(mut self, gravity_scale: f32) -> Self {
+        self.gravity_scale = gravity_scale;
         self
     }
 
+    /// Gives the body a different coefficient of friction for sliding along `direction`
+    /// (in body coordinates) than for sliding across it. Replaces `mu`.
+    pub fn with_anisotropic_friction(
+        mut self,
+        direction: Vec2,
+        mu_along: f32,
+        mu_across: f32,
+    ) -> Self {
+        self.anisotropic_friction = Some(AnisotropicFriction {
+            direction: direction.normalize_or(Vec2::X),
+            mu_along: mu_along.max(0.0),
+            mu_across: mu_across.max(0.0),
+        });
+        self
+    }
+
+    /// Contacts whose normal is within `max_angle` of `axis` (in body coordinates) use
+    /// `restitution` instead of the restitution of the body. Clamped to [0, 1] like it.
+    pub fn with_directional_restitution(
+        mut self,
+        axis: Vec2,
+        restitution: f32,
+        max_angle: f32,
+    ) -> Self {
+        self.directional_restitution = Some(DirectionalRestitution {
+            axis: axis.normalize_or(Vec2::Y),
+            restitution: restitution.clamp(0.0, 1.0),
+            max_angle,
+        });
+        self
+    }
+
+    /// the body uses this gravity instead of the world gravity, the gravity scale is ignored then
+    pub fn with_gravity_override(mut self, gravity: Vec2) -> Self {
+        self.gravity_override = Some(gravity);
+        self
+    }
+
+    /// Builds the body, panicking if the parameters are invalid. See `try_build`.
     pub fn build(self) -> RigidBody2D {
+        self.try_build()
+            .unwrap_or_else(|err| panic!("Invalid rigid body: {err}"))
+    }
+
+    /// Checks the mass and the shape before building the body.
+    pub fn try_build(self) -> Result<RigidBody2D, BuildError> {
+        if !self.inverse_mass.is_finite() || self.inverse_mass < 0.0 {
+            return Err(BuildError::InvalidInverseMass(self.inverse_mass));
+        }
+
+        match &self.shape {
+            Some(Collider::Circle { radius, .. }) if *radius <= 0.0 || radius.is_nan() => {
+                return Err(BuildError::InvalidRadius(*radius));
+            }
+            Some(Collider::AABB { min, max }) if min.x > max.x || min.y > max.y => {
+                return Err(BuildError::InvertedAABB {
+                    min: *min,
+                    max: *max,
+                });
+            }
+            Some(Collider::Chain { points }) if points.len() < 2 => {
+                return Err(BuildError::InvalidChain(points.len()));
+            }
+            _ => {}
+        }
+
+        let centroid = self
+            .shape
+            .as_ref()
+            .map_or(Vec2::ZERO, |shape| shape.centroid());
+        let local_center_of_mass = if self.com_at_origin {
+            Vec2::ZERO
+        } else {
+            centroid
+        };
+
         // calculate the inverse inertia of the body if a shape was provided
         let mut rb = RigidBody2D {
             position: self.position,
             angle: self.angle,
+            prev_position: self.position,
+            prev_angle: self.angle,
             angular_vel: self.angular_vel,
             vel: self.vel,
             accum_force: self.accum_force,
             accum_torque: self.accum_torque,
             inverse_mass: self.inverse_mass,
             inverse_inertia: self.inverse_inertia,
+            local_center_of_mass,
             is_static: self.is_static,
             restitution: self.restitution,
             mu: self.mu,
+            gravity_scale: self.gravity_scale,
+            suspended_gravity_scale: None,
+            gravity_override: self.gravity_override,
+            anisotropic_friction: self.anisotropic_friction,
+            directional_restitution: self.directional_restitution,
         };
 
         if rb.is_static {
             rb.inverse_mass = 0.0;
             rb.inverse_inertia = 0.0;
-            return rb;
+            return Ok(rb);
+        }
+
+        // an AABB stays axis aligned, so it can't turn. Its contacts only have a single point in
+        // the middle of the overlap, friction there would make it spin like a wheel
+        let is_aabb = self.shape.as_ref().is_some_and(|shape| shape.is_aabb());
+        if self.fixed_rotation.unwrap_or(is_aabb) {
+            rb.inverse_inertia = 0.0;
+            rb.angular_vel = 0.0;
+            return Ok(rb);
         }
 
         if let Some(shape) = self.shape {
-            match shape {
-                Collider::AABB { min, max } => {
-                    let h = (max.y - min.y).abs();
-                    let w = (max.x - min.x).abs();
-                    let m = 1.0 / self.inverse_mass;
-                    rb.inverse_inertia = (1.0 / 12.0) * m * (w * w + h * h);
-                }
-                Collider::Circle { radius, .. } => {
-                    let m = 1.0 / self.inverse_mass;
-                    rb.inverse_inertia = 0.5 * m * radius * radius;
-                }
+            let mass = 1.0 / self.inverse_mass;
+            let mut inertia = shape.inertia(mass);
+            // parallel axis theorem, the shape turns around a point away from its center
+            if self.com_at_origin {
+                inertia += mass * centroid.length_squared();
             }
+            if inertia > 0.0 {
+                rb.inverse_inertia = 1.0 / inertia;
+            }
+        }
+
+        if let Some(angular_momentum) = self.angular_momentum {
+            rb.angular_vel = angular_momentum * rb.inverse_inertia;
         }
 
-        rb
+        Ok(rb)
     }
 }
 
+/// A snapshot of the kinematic state of a body.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyState {
+    pub position: Vec2,
+    pub angle: f32,
+    pub vel: Vec2,
+    pub angular_vel: f32,
+}
+
+#[derive(Clone)]
 pub struct RigidBody2D {
     pub position: Vec2,
     pub angle: f32,
+    // the state before the last step, used to interpolate between steps when rendering
+    pub prev_position: Vec2,
+    pub prev_angle: f32,
     // acc: Vec2,
     pub angular_vel: f32,
     pub vel: Vec2,
@@ -132,28 +385,259 @@ pub struct RigidBody2D {
     pub accum_torque: f32,
     pub inverse_mass: f32,
     pub inverse_inertia: f32,
+    // relative to `position`, the point the body turns around
+    pub local_center_of_mass: Vec2,
     pub is_static: bool,
     pub restitution: f32,
     pub mu: f32, // coefficient of friction for this object
-                 // this is not accurate but i will do it just like with restitution
+    // this is not accurate but i will do it just like with restitution
+
+    // how strongly the world gravity affects the body
+    pub gravity_scale: f32,
+    // the gravity scale from before `suspend_gravity`, restored by `resume_gravity`
+    suspended_gravity_scale: Option<f32>,
+    // replaces the world gravity (and the scale) for this body if set
+    pub gravity_override: Option<Vec2>,
+    // replaces `mu` if set
+    pub anisotropic_friction: Option<AnisotropicFriction>,
+    // replaces `restitution` for contacts along its axis if set
+    pub directional_restitution: Option<DirectionalRestitution>,
 }
 
 impl RigidBody2D {
+    pub fn state(&self) -> BodyState {
+        BodyState {
+            position: self.position,
+            angle: self.angle,
+            vel: self.vel,
+            angular_vel: self.angular_vel,
+        }
+    }
+
+    /// false if the position, angle or one of the velocities became NaN or infinite
+    pub fn is_finite(&self) -> bool {
+        self.position.is_finite()
+            && self.angle.is_finite()
+            && self.vel.is_finite()
+            && self.angular_vel.is_finite()
+    }
+
+    /// Puts a body whose state is no longer finite back to where it was before the last step,
+    /// or the origin if that isn't finite either, and stops it.
+    pub fn reset_to_safe_state(&mut self) {
+        if !self.prev_position.is_finite() {
+            self.prev_position = Vec2::ZERO;
+        }
+        if !self.prev_angle.is_finite() {
+            self.prev_angle = 0.0;
+        }
+        self.position = self.prev_position;
+        self.angle = self.prev_angle;
+        self.vel = Vec2::ZERO;
+        self.angular_vel = 0.0;
+        self.accum_force = Vec2::ZERO;
+        self.accum_torque = 0.0;
+    }
+
+    /// Restores a snapshot. The previous state is set as well so that nothing is interpolated.
+    pub fn set_state(&mut self, state: BodyState) {
+        self.position = state.position;
+        self.prev_position = state.position;
+        self.angle = state.angle;
+        self.prev_angle = state.angle;
+        self.vel = state.vel;
+        self.angular_vel = state.angular_vel;
+    }
+
     pub fn apply_force(&mut self, force: Vec2) {
         self.accum_force += force;
     }
 
+    /// Applies a force given in body coordinates, so it turns with the body.
+    /// +x is `forward()` and +y is to the left of it.
+    pub fn apply_local_force(&mut self, force: Vec2) {
+        self.apply_force(Vec2::from_angle(self.angle).rotate(force));
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.accum_torque += torque;
+    }
+
+    /// the direction the body faces in world coordinates, +x at an angle of 0
+    pub fn forward(&self) -> Vec2 {
+        Vec2::from_angle(self.angle)
+    }
+
+    /// `forward()` turned 90 degrees clockwise
+    pub fn right(&self) -> Vec2 {
+        -self.forward().perp()
+    }
+
+    /// the center of mass in world coordinates
+    pub fn center_of_mass(&self) -> Vec2 {
+        self.position + self.local_center_of_mass
+    }
+
     pub fn apply_impulse(&mut self, impulse: Vec2) {
         self.vel += impulse * self.inverse_mass;
     }
 
+    /// Applies an impulse at a point in world coordinates. An impulse that doesn't point
+    /// through the center of the body also changes its angular velocity.
+    pub fn apply_impulse_at_point(&mut self, impulse: Vec2, world_point: Vec2) {
+        let r = world_point - self.center_of_mass();
+        self.vel += impulse * self.inverse_mass;
+        self.angular_vel += r.perp_dot(impulse) * self.inverse_inertia;
+    }
+
+    /// the velocity of a point of the body, including the part that comes from the rotation
+    pub fn velocity_at_point(&self, world_point: Vec2) -> Vec2 {
+        let r = world_point - self.center_of_mass();
+        self.vel + self.angular_vel * r.perp()
+    }
+
+    /// Applies the impulse at `world_point` that makes the velocity of that point equal to
+    /// `desired_vel`, split between linear and angular velocity according to the mass and inertia.
+    /// Returns the applied impulse.
+    pub fn set_velocity_at_point(&mut self, world_point: Vec2, desired_vel: Vec2) -> Vec2 {
+        if self.is_static {
+            return Vec2::ZERO;
+        }
+
+        let r = world_point - self.center_of_mass();
+        let velocity_change = desired_vel - self.velocity_at_point(world_point);
+
+        // the velocity change at the point for an impulse j is
+        // inverse_mass * j + inverse_inertia * (r x j) * perp(r) = k * j
+        let r_perp = r.perp();
+        let k = Mat2::from_diagonal(Vec2::splat(self.inverse_mass))
+            + Mat2::from_cols(r_perp * r_perp.x, r_perp * r_perp.y) * self.inverse_inertia;
+        if k.determinant().abs() <= f32::EPSILON {
+            return Vec2::ZERO;
+        }
+
+        let impulse = k.inverse() * velocity_change;
+        self.apply_impulse_at_point(impulse, world_point);
+        impulse
+    }
+
+    /// The coefficient of friction for sliding along the world direction `sliding_dir`.
+    pub fn mu_in_direction(&self, sliding_dir: Vec2) -> f32 {
+        let Some(friction) = self.anisotropic_friction else {
+            return self.mu;
+        };
+        let direction = Vec2::from_angle(self.angle).rotate(friction.direction);
+        let sliding_dir = sliding_dir.normalize_or_zero();
+        let along = friction.mu_along * sliding_dir.dot(direction);
+        let across = friction.mu_across * sliding_dir.perp_dot(direction);
+        (along * along + across * across).sqrt()
+    }
+
+    /// The restitution for a contact with the world normal `normal`.
+    /// This is just `restitution` unless the body has a directional restitution.
+    pub fn restitution_for_normal(&self, normal: Vec2) -> f32 {
+        let Some(directional) = self.directional_restitution else {
+            return self.restitution;
+        };
+        let axis = Vec2::from_angle(self.angle).rotate(directional.axis);
+        let alignment = normal.normalize_or_zero().dot(axis).abs();
+        if alignment >= directional.max_angle.cos() {
+            directional.restitution
+        } else {
+            self.restitution
+        }
+    }
+
+    /// Changes the mass without changing the velocity, so the momentum changes with it. The inertia
+    /// is scaled by the same factor, which is what recomputing it from the collider would give.
+    /// A mass of zero is clamped to a tiny positive mass. Negative and NaN masses as well as static
+    /// bodies are rejected and false is returned.
+    pub fn set_mass(&mut self, mass: f32) -> bool {
+        if self.is_static || mass.is_nan() || mass < 0.0 {
+            return false;
+        }
+        let new_inverse_mass = 1.0 / mass.max(MIN_MASS);
+        // I is proportional to m for a fixed shape. A body with infinite mass has no finite
+        // inertia to scale, so it keeps its inverse inertia
+        if self.inverse_mass > 0.0 {
+            self.inverse_inertia *= new_inverse_mass / self.inverse_mass;
+        }
+        self.inverse_mass = new_inverse_mass;
+        true
+    }
+
+    /// the mass reconstructed from the inverse mass, None for static and infinitely heavy bodies
+    pub fn mass(&self) -> Option<f32> {
+        if self.is_static || self.inverse_mass == 0.0 {
+            return None;
+        }
+        Some(1.0 / self.inverse_mass)
+    }
+
+    /// The translational plus the rotational kinetic energy. Bodies with an infinite mass
+    /// or inertia don't contribute the respective part.
+    pub fn kinetic_energy(&self) -> f32 {
+        let mut energy = 0.0;
+        if self.inverse_mass > 0.0 {
+            energy += 0.5 * self.vel.length_squared() / self.inverse_mass;
+        }
+        if self.inverse_inertia > 0.0 {
+            energy += 0.5 * self.angular_vel * self.angular_vel / self.inverse_inertia;
+        }
+        energy
+    }
+
+    /// Makes the body float, e.g. while the player holds it, until `resume_gravity` is called.
+    /// Only the gravity scale is set to 0, a gravity override still applies.
+    pub fn suspend_gravity(&mut self) {
+        if self.suspended_gravity_scale.is_none() {
+            self.suspended_gravity_scale = Some(self.gravity_scale);
+            self.gravity_scale = 0.0;
+        }
+    }
+
+    /// Restores the gravity scale from before `suspend_gravity`.
+    pub fn resume_gravity(&mut self) {
+        if let Some(gravity_scale) = self.suspended_gravity_scale.take() {
+            self.gravity_scale = gravity_scale;
+        }
+    }
+
+    pub fn is_gravity_suspended(&self) -> bool {
+        self.suspended_gravity_scale.is_some()
+    }
+
+    /// the gravity acceleration this body experiences in a world with `world_gravity`
+    pub fn gravity(&self, world_gravity: Vec2) -> Vec2 {
+        self.gravity_override
+            .unwrap_or(world_gravity * self.gravity_scale)
+    }
+
+    /// Adds the velocity the gravity gives the body during `dt`, without moving it.
+    /// Used when the gravity is applied before the contacts are solved, see `GravitySequencing`.
+    pub fn apply_gravity(&mut self, dt: f32, world_gravity: Vec2) {
+        if self.inverse_mass == 0.0 || self.is_static {
+            return;
+        }
+        self.vel += self.gravity(world_gravity) * dt;
+    }
+
     /// update using verlet integration
-    pub fn update(&mut self, dt: f32) {
+    /// gravity is an acceleration, so it is added to the velocity directly instead of
+    /// being turned into a force first
+    pub fn update(&mut self, dt: f32, gravity: Vec2) {
+        self.integrate(dt, self.gravity(gravity));
+    }
+
+    /// Moves the body by its velocity after adding `acceleration` and the accumulated forces.
+    /// Unlike `update` the gravity is not added.
+    pub fn integrate(&mut self, dt: f32, acceleration: Vec2) {
         if self.inverse_mass == 0.0 || self.is_static {
             return;
         }
         // NOTE: this is euler
-        let new_vel = self.vel + dt * self.inverse_mass * self.accum_force;
+        let acceleration = acceleration + self.inverse_mass * self.accum_force;
+        let new_vel = self.vel + dt * acceleration;
         let new_pos = self.position + new_vel * dt;
 
         let new_ang_vel = self.angular_vel + dt * self.inverse_inertia * self.accum_torque;
@@ -174,3 +658,189 @@ impl RigidBody2D {
         self.accum_torque = 0.0;
     }
 }
+
+/// The impulse that makes `body` rise to `height` above its current position under `gravity`,
+/// taking the velocity it already has against the gravity into account. Returns zero if the body
+/// can't be moved, already rises high enough, or there is no gravity to pull it back down.
+pub fn impulse_for_jump_height(body: &RigidBody2D, height: f32, gravity: Vec2) -> Vec2 {
+    let gravity = body.gravity(gravity);
+    let Some(up) = (-gravity).try_normalize() else {
+        return Vec2::ZERO;
+    };
+    if body.is_static || body.inverse_mass == 0.0 {
+        return Vec2::ZERO;
+    }
+
+    // v^2 = 2 * g * h, since the velocity is zero at the apex
+    let takeoff_speed = (2.0 * gravity.length() * height.max(0.0)).sqrt();
+    let missing_speed = f32::max(takeoff_speed - body.vel.dot(up), 0.0);
+    up * missing_speed / body.inverse_mass
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn out_of_range_restitution_and_mu_are_clamped() {
+        let body = RigidBody2DBuilder::new()
+            .with_restitution(2.0)
+            .with_mu(-0.5)
+            .build();
+        assert_relative_eq!(body.restitution, 1.0);
+        assert_relative_eq!(body.mu, 0.0);
+
+        let body = RigidBody2DBuilder::new().with_restitution(-1.0).build();
+        assert_relative_eq!(body.restitution, 0.0);
+
+        let body = RigidBody2DBuilder::new()
+            .with_restitution(0.7)
+            .with_mu(0.4)
+            .build();
+        assert_relative_eq!(body.restitution, 0.7);
+        assert_relative_eq!(body.mu, 0.4);
+    }
+
+    #[test]
+    fn angular_momentum_is_divided_by_the_inertia() {
+        let shape = Collider::Circle {
+            offset: Vec2::ZERO,
+            radius: 1.0,
+        };
+        // I = 1/2 * m * r² = 1/2 * 4 * 1
+        let body = RigidBody2DBuilder::new()
+            .with_shape(shape)
+            .with_inverse_mass(0.25)
+            .with_angular_momentum(6.0)
+            .build();
+        assert_relative_eq!(body.angular_vel, 3.0);
+    }
+
+    #[test]
+    fn aabb_bodies_have_fixed_rotation_unless_asked_otherwise() {
+        let shape = Collider::AABB {
+            min: vec2(-0.5, -0.5),
+            max: vec2(0.5, 0.5),
+        };
+        let body = RigidBody2DBuilder::new()
+            .with_shape(shape.clone())
+            .with_angular_vel(2.0)
+            .build();
+        assert_eq!(body.inverse_inertia, 0.0);
+        assert_eq!(body.angular_vel, 0.0);
+
+        let body = RigidBody2DBuilder::new()
+            .with_shape(shape)
+            .with_angular_vel(2.0)
+            .with_fixed_rotation(false)
+            .build();
+        assert!(body.inverse_inertia > 0.0);
+        assert_relative_eq!(body.angular_vel, 2.0);
+    }
+
+    #[test]
+    fn built_body_has_the_reciprocal_of_the_shape_inertia() {
+        let shapes = [
+            Collider::Circle {
+                offset: Vec2::ZERO,
+                radius: 2.0,
+            },
+            Collider::AABB {
+                min: vec2(-1.0, -2.0),
+                max: vec2(1.0, 2.0),
+            },
+        ];
+        for shape in shapes {
+            let body = RigidBody2DBuilder::new()
+                .with_shape(shape.clone())
+                .with_inverse_mass(0.5)
+                .with_fixed_rotation(false)
+                .build();
+            assert_relative_eq!(body.inverse_inertia, 1.0 / shape.inertia(2.0));
+        }
+    }
+
+    #[test]
+    fn set_mass_keeps_the_velocity_and_scales_the_inertia() {
+        let shape = Collider::Circle {
+            offset: Vec2::ZERO,
+            radius: 1.0,
+        };
+        let mut body = RigidBody2DBuilder::new()
+            .with_shape(shape)
+            .with_vel(vec2(2.0, 0.0))
+            .with_angular_vel(1.0)
+            .build();
+        let inverse_inertia = body.inverse_inertia;
+
+        assert!(body.set_mass(4.0));
+        assert_relative_eq!(body.inverse_mass, 0.25);
+        assert_relative_eq!(body.inverse_inertia, inverse_inertia / 4.0);
+        assert_relative_eq!(body.vel.x, 2.0);
+        assert_relative_eq!(body.angular_vel, 1.0);
+
+        assert!(!body.set_mass(-1.0));
+        assert_relative_eq!(body.inverse_mass, 0.25);
+        assert!(body.set_mass(0.0));
+        assert!(body.inverse_mass.is_finite());
+    }
+
+    #[test]
+    fn directional_restitution_only_applies_along_its_axis() {
+        let mut trampoline = RigidBody2DBuilder::new()
+            .make_static()
+            .with_restitution(0.1)
+            .with_directional_restitution(Vec2::Y, 0.9, 30f32.to_radians())
+            .build();
+        assert_relative_eq!(trampoline.restitution_for_normal(Vec2::Y), 0.9);
+        assert_relative_eq!(trampoline.restitution_for_normal(-Vec2::Y), 0.9);
+        assert_relative_eq!(trampoline.restitution_for_normal(vec2(1.0, 1.0)), 0.1);
+        assert_relative_eq!(trampoline.restitution_for_normal(Vec2::X), 0.1);
+
+        // the axis turns with the body
+        trampoline.angle = std::f32::consts::FRAC_PI_2;
+        assert_relative_eq!(trampoline.restitution_for_normal(Vec2::X), 0.9);
+        assert_relative_eq!(trampoline.restitution_for_normal(Vec2::Y), 0.1);
+    }
+
+    #[test]
+    fn jump_impulse_reaches_the_apex_height() {
+        let gravity = vec2(0.0, -10.0);
+        let mut body = RigidBody2DBuilder::new().with_inverse_mass(0.5).build();
+        let impulse = impulse_for_jump_height(&body, 5.0, gravity);
+        // v = sqrt(2 * g * h) = 10, times the mass of 2
+        assert_relative_eq!(impulse.x, 0.0);
+        assert_relative_eq!(impulse.y, 20.0, epsilon = 1e-4);
+
+        body.apply_impulse(impulse);
+        let dt = 1.0 / 1000.0;
+        while body.vel.y > 0.0 {
+            body.update(dt, gravity);
+        }
+        assert_relative_eq!(body.position.y, 5.0, epsilon = 0.02);
+    }
+
+    #[test]
+    fn center_of_mass_follows_the_offset_unless_it_is_kept_at_the_origin() {
+        let shape = Collider::Circle {
+            offset: vec2(2.0, 1.0),
+            radius: 0.5,
+        };
+        let position = vec2(3.0, 4.0);
+        let body = RigidBody2DBuilder::new()
+            .with_position(position)
+            .with_shape(shape.clone())
+            .build();
+        assert_eq!(body.center_of_mass(), position + vec2(2.0, 1.0));
+
+        let turret = RigidBody2DBuilder::new()
+            .with_position(position)
+            .with_shape(shape)
+            .with_com_at_origin(true)
+            .build();
+        assert_eq!(turret.center_of_mass(), position);
+        // turning around a point away from the shape is harder
+        assert!(turret.inverse_inertia < body.inverse_inertia);
+    }
+}
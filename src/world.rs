@@ -0,0 +1,3066 @@
+use crate::Camera;
+use crate::Contact;
+use crate::UP;
+use crate::batch::LineBatch;
+use crate::collider::*;
+use crate::filter::*;
+use crate::force::*;
+use crate::island::*;
+use crate::joint::*;
+use crate::object::*;
+use crate::rigid_body::*;
+use macroquad::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+pub const GRAVITY_EARTH: Vec2 = Vec2::new(-9.81 * UP.x, -9.81 * UP.y);
+pub const GRAVITY_MOON: Vec2 = Vec2::new(-1.62 * UP.x, -1.62 * UP.y);
+pub const GRAVITY_ZERO: Vec2 = Vec2::ZERO;
+
+type PreSolveCallback = Box<dyn Fn(&Contact, &Object, &Object) -> bool>;
+type RestitutionCallback = Box<dyn Fn(&Object, &Object) -> Option<f32>>;
+
+/// Identifies an object in a world. Unlike an index it stays valid when other objects are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectHandle(pub usize);
+
+/// A summary of how two objects collided during the last step.
+#[derive(Debug, Clone)]
+pub struct CollisionEvent {
+    pub a: ObjectHandle,
+    pub b: ObjectHandle,
+    pub point: Vec2,
+    pub normal: Vec2, // from a's point of view
+    // the deepest penetration of the pair during the step
+    pub pen_depth: f32,
+    // the speed with which the objects approached each other along the normal before the collision
+    pub approach_speed: f32,
+    // the normal impulse summed over all solver iterations
+    pub normal_impulse: f32,
+    // the kinetic energy of both bodies together before and after the collision was resolved.
+    // The difference is the energy the collision dissipated
+    pub kinetic_energy_before: f32,
+    pub kinetic_energy_after: f32,
+}
+
+/// Two objects that penetrated deeper than `World::max_penetration` and were pulled apart at once.
+/// Usually a sign of a failed solve or of an object that was spawned inside another one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmergencySeparation {
+    pub a: ObjectHandle,
+    pub b: ObjectHandle,
+    // the penetration before the separation
+    pub pen_depth: f32,
+}
+
+/// Gives contacts between fast bodies more solver iterations than resting ones.
+/// The number of iterations grows linearly with the relative speed of the bodies at the start
+/// of the step, from `min_iterations` when resting to `max_iterations` at `full_speed` and above.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationLod {
+    pub min_iterations: usize,
+    pub max_iterations: usize,
+    pub full_speed: f32,
+}
+
+impl IterationLod {
+    pub fn iterations_for_speed(&self, speed: f32) -> usize {
+        let max_iterations = self.max_iterations.max(self.min_iterations);
+        let fraction = if self.full_speed > 0.0 {
+            (speed / self.full_speed).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let extra = (max_iterations - self.min_iterations) as f32 * fraction;
+        self.min_iterations + extra.ceil() as usize
+    }
+}
+
+/// When the gravity of a step is added to the velocity of the bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GravitySequencing {
+    // the contacts are solved first and the gravity is added while moving the bodies. A resting
+    // body then moves into what it rests on by the gravity of one step at the end of every step
+    AfterSolve,
+    // the gravity is added before the contacts are solved, so they see it and cancel it.
+    // Resting bodies end the step without any velocity into what they rest on
+    BeforeSolve,
+}
+
+/// Numbers about the last step, for profiling and debugging.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StepStats {
+    // groups of bodies that were solved independently of each other, see `Island`
+    pub island_count: usize,
+    // pairs of objects whose colliders were tested against each other
+    pub pair_tests: usize,
+    pub contact_count: usize,
+    // how often a contact was solved, summed over all contacts and iterations
+    pub contact_solves: usize,
+    // the fastest approach along a contact normal that was left after the last velocity
+    // iteration. If it is above `World::convergence_tolerance` the solver didn't converge,
+    // and more iterations or a smaller timestep would make the contacts more accurate
+    pub residual: f32,
+    pub converged: bool,
+}
+
+/// Reported when an object starts or stops overlapping a sensor.
+/// The handles are ordered so that the smaller one comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorEvent {
+    Enter(ObjectHandle, ObjectHandle),
+    Exit(ObjectHandle, ObjectHandle),
+}
+
+pub struct World {
+    objects: Vec<Object>,
+    // handles[i] is the handle of objects[i]
+    handles: Vec<ObjectHandle>,
+    next_handle: usize,
+    // the acceleration every body experiences
+    pub gravity: Vec2,
+    pub gravity_sequencing: GravitySequencing,
+    // the accuracy increases with the number of iterations
+    pub velocity_iterations: usize,
+    // the approach speed the contacts may have left after the velocity iterations for the step
+    // to count as converged, see `StepStats::converged`
+    pub convergence_tolerance: f32,
+    // if set, every contact gets a number of iterations depending on how fast its bodies move
+    // instead of `velocity_iterations`. Ignored with `detect_every_iteration`
+    pub iteration_lod: Option<IterationLod>,
+    // passes over all joints per step, independent of the velocity iterations.
+    // stiff joints need more of them to stay stable
+    pub joint_iterations: usize,
+    // by default the contacts are detected once per step and every velocity iteration works on
+    // that set. If this is set they are detected again before every iteration instead
+    pub detect_every_iteration: bool,
+    // solve the contacts ordered by the positions of their bodies instead of by the position of
+    // their objects in the object list, so the result doesn't depend on the order the objects
+    // were added in. Costs a sort and another narrow phase test for some of the contacts
+    pub deterministic: bool,
+    // bodies faster than this are swept against the other objects so they can't tunnel through
+    // them, slower ones only use the discrete collision detection. None disables the sweeps
+    pub ccd_speed_threshold: Option<f32>,
+    // passes that push overlapping bodies apart by moving them directly, without touching their
+    // velocity. If this is 0 the velocity passes correct the penetration with a bias instead
+    pub position_iterations: usize,
+    // contacts penetrating deeper than this are separated completely at the start of the step
+    // instead of slowly by the bias or the position iterations, see `emergency_separations`
+    pub max_penetration: Option<f32>,
+    // objects whose names match one of its rules don't collide, see `NameFilter::ignore_pair`
+    pub name_filter: NameFilter,
+    // the maximum number of contacts resolved per iteration, the most urgent ones are resolved first.
+    // the rest keeps its penetration and is picked up again in the next frame
+    pub contact_budget: Option<usize>,
+    // damps the spin of bodies that rest on something, 0 disables it
+    pub resting_angular_damping: f32,
+    // extra damping for bodies that touch something during a step, 0 disables it
+    pub contact_linear_damping: f32,
+    pub contact_angular_damping: f32,
+    // contacts that approach slower than this don't bounce, so bouncing bodies come to rest
+    // after a finite number of bounces instead of bouncing lower and lower forever
+    pub restitution_threshold: f32,
+    // contacts that approach only as fast as the gravity of a single step makes them don't bounce.
+    // Without it a body resting on a bouncy floor keeps bouncing off it by tiny amounts
+    pub suppress_gravity_bounce: bool,
+    // contacts whose relative velocity is below this are considered resting
+    pub resting_velocity_threshold: f32,
+    // every contact that was resolved during the last step. The buffer is reused between steps
+    pub contacts: Vec<Contact>,
+    // reused by the position iterations and the sensor check so they don't allocate every step
+    scratch_contacts: Vec<Contact>,
+    // one event per pair of colliding objects during the last step
+    collision_events: Vec<CollisionEvent>,
+    step_stats: StepStats,
+    // fill `collision_log` with a readable line per collision event, see `collision_log`
+    pub log_collisions: bool,
+    collision_log: Vec<String>,
+    // the pairs that overlapped a sensor at the end of the last step
+    sensor_overlaps: HashSet<(ObjectHandle, ObjectHandle)>,
+    joints: Vec<DistanceJoint>,
+    point_gravities: Vec<PointGravity>,
+    // the pairs whose penetration was corrected in the last step, see `update_correcting_pairs`
+    correcting_pairs: HashSet<(ObjectHandle, ObjectHandle)>,
+    sensor_events: Vec<SensorEvent>,
+    emergency_separations: Vec<EmergencySeparation>,
+    // one entry per step while recording, holding the state of every body by the handle of its
+    // object, so objects that are added or removed later don't receive the wrong states
+    recorded_frames: Vec<Vec<(ObjectHandle, BodyState)>>,
+    is_recording: bool,
+    // check every body for NaN and infinity at the end of each step, see `invalid_bodies`
+    pub validate_every_step: bool,
+    // put the bodies found by `validate_every_step` back into a safe state
+    pub reset_invalid_bodies: bool,
+    invalid_bodies: Vec<ObjectHandle>,
+    // the min and max corner of the region that dynamic bodies wrap around in, see `set_wrap`
+    wrap: Option<(Vec2, Vec2)>,
+    // the timestep used by `update`
+    pub fixed_dt: f32,
+    // the frame time that has not been simulated yet
+    accumulator: f32,
+    pre_solve: Option<PreSolveCallback>,
+    // what the pre-solve callback answered for each pair during this step, so it is only asked once
+    pre_solve_answers: HashMap<(ObjectHandle, ObjectHandle), bool>,
+    restitution_override: Option<RestitutionCallback>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            objects: vec![],
+            handles: vec![],
+            next_handle: 0,
+            gravity: GRAVITY_EARTH,
+            gravity_sequencing: GravitySequencing::AfterSolve,
+            velocity_iterations: 10,
+            convergence_tolerance: 0.01,
+            iteration_lod: None,
+            joint_iterations: 10,
+            detect_every_iteration: false,
+            deterministic: false,
+            ccd_speed_threshold: None,
+            position_iterations: 0,
+            max_penetration: None,
+            name_filter: NameFilter::new(),
+            contact_budget: None,
+            resting_angular_damping: 0.0,
+            contact_linear_damping: 0.0,
+            contact_angular_damping: 0.0,
+            restitution_threshold: 0.0,
+            suppress_gravity_bounce: true,
+            resting_velocity_threshold: 0.1,
+            contacts: vec![],
+            scratch_contacts: vec![],
+            collision_events: vec![],
+            step_stats: StepStats::default(),
+            log_collisions: false,
+            collision_log: vec![],
+            sensor_overlaps: HashSet::new(),
+            correcting_pairs: HashSet::new(),
+            joints: vec![],
+            point_gravities: vec![],
+            sensor_events: vec![],
+            emergency_separations: vec![],
+            recorded_frames: vec![],
+            is_recording: false,
+            validate_every_step: false,
+            reset_invalid_bodies: false,
+            invalid_bodies: vec![],
+            wrap: None,
+            fixed_dt: 1.0 / 60.0,
+            accumulator: 0.0,
+            pre_solve: None,
+            pre_solve_answers: HashMap::new(),
+            restitution_override: None,
+        }
+    }
+
+    pub fn add(&mut self, object: Object) -> ObjectHandle {
+        let handle = ObjectHandle(self.next_handle);
+        self.next_handle += 1;
+        self.objects.push(object);
+        self.handles.push(handle);
+        handle
+    }
+
+    /// Removes the object together with the joints attached to it. The contacts of the last step
+    /// are dropped as well, since they refer to objects by index.
+    pub fn remove(&mut self, handle: ObjectHandle) -> Option<Object> {
+        let index = self.index_of(handle)?;
+        self.handles.remove(index);
+        self.joints
+            .retain(|joint| joint.a != handle && joint.b != handle);
+        self.point_gravities
+            .retain(|gravity| gravity.attractor != handle);
+        self.contacts.clear();
+        Some(self.objects.remove(index))
+    }
+
+    /// Breaks a body into `pieces` circles that fill its bounds on a grid. The mass is split evenly
+    /// between the fragments and each one moves like the point of the original body it came from,
+    /// so the momentum is conserved if the fragments fill the grid, like 4, 6 or 9 of them, and
+    /// approximately conserved otherwise, when the grid is not centered on the body. Static bodies and objects without a body or
+    /// collider don't break, in that case nothing happens and no handles are returned.
+    pub fn fracture(&mut self, handle: ObjectHandle, pieces: usize) -> Vec<ObjectHandle> {
+        let is_breakable = self.get(handle).is_some_and(|object| {
+            matches!(
+                (&object.body, &object.collider),
+                (Some(body), Some(_)) if !body.is_static && body.inverse_mass > 0.0
+            )
+        });
+        if pieces == 0 || !is_breakable {
+            return vec![];
+        }
+        let Some(Object {
+            body: Some(body),
+            collider: Some(collider),
+            color,
+            name,
+            ..
+        }) = self.remove(handle)
+        else {
+            return vec![];
+        };
+        let (min, max) = collider.world_bounds(body.position);
+
+        let cols = (pieces as f32).sqrt().ceil() as usize;
+        let rows = pieces.div_ceil(cols);
+        let cell_size = (max - min) / vec2(cols as f32, rows as f32);
+        let shape = Collider::Circle {
+            offset: Vec2::ZERO,
+            // a little smaller than the cell so the fragments don't start out overlapping
+            radius: 0.45 * cell_size.min_element(),
+        };
+
+        let mut fragments = Vec::with_capacity(pieces);
+        for i in 0..pieces {
+            let cell = vec2((i % cols) as f32, (i / cols) as f32);
+            let position = min + (cell + Vec2::splat(0.5)) * cell_size;
+            let mut fragment = RigidBody2DBuilder::new()
+                .with_position(position)
+                .with_shape(shape.clone())
+                .with_inverse_mass(body.inverse_mass * pieces as f32)
+                .with_motion(body.velocity_at_point(position), body.angular_vel)
+                .with_gravity_scale(body.gravity_scale)
+                .with_restitution(body.restitution)
+                .with_mu(body.mu)
+                .build();
+            fragment.gravity_override = body.gravity_override;
+            let fragment = ObjectBuilder::new()
+                .with_body(fragment)
+                .with_collider(shape.clone())
+                .with_color(color)
+                .with_name(format!("{name}_fragment_{i}"))
+                .build();
+            fragments.push(self.add(fragment));
+        }
+        fragments
+    }
+
+    /// Moves all objects of `other` into this world, translated by `offset`.
+    /// The objects get new handles, the returned map translates the handles of `other` into them.
+    /// Joints and point gravities attached to objects that are no longer in `other` are dropped.
+    pub fn import(&mut self, other: World, offset: Vec2) -> HashMap<ObjectHandle, ObjectHandle> {
+        let mut handle_map = HashMap::new();
+        for (mut object, old_handle) in other.objects.into_iter().zip(other.handles) {
+            if let Some(body) = &mut object.body {
+                body.position += offset;
+                body.prev_position += offset;
+            }
+            let new_handle = self.add(object);
+            handle_map.insert(old_handle, new_handle);
+        }
+        for mut joint in other.joints {
+            let (Some(&a), Some(&b)) = (handle_map.get(&joint.a), handle_map.get(&joint.b)) else {
+                continue;
+            };
+            joint.a = a;
+            joint.b = b;
+            self.joints.push(joint);
+        }
+        for mut gravity in other.point_gravities {
+            let Some(&attractor) = handle_map.get(&gravity.attractor) else {
+                continue;
+            };
+            gravity.attractor = attractor;
+            self.point_gravities.push(gravity);
+        }
+        handle_map
+    }
+
+    /// Adds a copy of the object, moved by `offset` and at rest. Joints and point gravities
+    /// attached to the original are not copied. Returns None if the handle is invalid.
+    pub fn duplicate(&mut self, handle: ObjectHandle, offset: Vec2) -> Option<ObjectHandle> {
+        let mut copy = self.get(handle)?.clone();
+        if let Some(body) = &mut copy.body {
+            body.set_state(BodyState {
+                position: body.position + offset,
+                angle: body.angle,
+                vel: Vec2::ZERO,
+                angular_vel: 0.0,
+            });
+            body.accum_force = Vec2::ZERO;
+            body.accum_torque = 0.0;
+        }
+        Some(self.add(copy))
+    }
+
+    /// Adds an immovable object, for the floor, walls and the rest of the level geometry.
+    pub fn add_static(
+        &mut self,
+        collider: Collider,
+        position: Vec2,
+        material: PhysicsMaterial,
+    ) -> ObjectHandle {
+        let body = RigidBody2DBuilder::new()
+            .make_static()
+            .with_position(position)
+            .with_shape(collider.clone())
+            .with_material(material)
+            .build();
+        let object = ObjectBuilder::new()
+            .with_body(body)
+            .with_collider(collider)
+            .with_color(GRAY)
+            .with_name("static".to_string())
+            .build();
+        self.add(object)
+    }
+
+    pub fn add_joint(&mut self, joint: DistanceJoint) {
+        self.joints.push(joint);
+    }
+
+    /// Makes the world toroidal like in asteroids: a dynamic body that leaves the region between
+    /// `min` and `max` on one side comes back in on the opposite side, keeping its velocity.
+    /// Only the position of the body wraps, bodies on opposite sides of the seam don't collide.
+    pub fn set_wrap(&mut self, min: Vec2, max: Vec2) {
+        self.wrap = Some((min.min(max), min.max(max)));
+    }
+
+    pub fn clear_wrap(&mut self) {
+        self.wrap = None;
+    }
+
+    /// Connects two objects with a joint that keeps their current distance.
+    /// Returns false if one of the objects doesn't exist or has no body.
+    pub fn add_distance_joint(&mut self, a: ObjectHandle, b: ObjectHandle, stiffness: f32) -> bool {
+        let position = |handle| Some(self.get(handle)?.body.as_ref()?.position);
+        let (Some(position_a), Some(position_b)) = (position(a), position(b)) else {
+            return false;
+        };
+        let rest_length = position_a.distance(position_b);
+        self.add_joint(DistanceJoint::new(a, b, rest_length, stiffness));
+        true
+    }
+
+    pub fn joints(&self) -> &[DistanceJoint] {
+        &self.joints
+    }
+
+    /// Adds an attractor that pulls every other dynamic body towards it on top of the
+    /// world gravity. Set the gravity to `GRAVITY_ZERO` for a space scene.
+    pub fn add_point_gravity(&mut self, gravity: PointGravity) {
+        self.point_gravities.push(gravity);
+    }
+
+    pub fn point_gravities(&self) -> &[PointGravity] {
+        &self.point_gravities
+    }
+
+    /// A ready-made scene without world gravity: a static planet at `center` with a `PointGravity`
+    /// and `satellites` bodies that circle it on orbits of growing radius, each started with the
+    /// tangential speed of a circular orbit. Returns the handle of the planet.
+    pub fn spawn_orbital_system(&mut self, center: Vec2, satellites: usize) -> ObjectHandle {
+        let planet_radius = 4.0;
+        self.gravity = GRAVITY_ZERO;
+        let planet = self.add_static(
+            Collider::Circle {
+                offset: Vec2::ZERO,
+                radius: planet_radius,
+            },
+            center,
+            PhysicsMaterial::default(),
+        );
+        let gravity = PointGravity::new(planet, 1500.0);
+        self.add_point_gravity(gravity);
+
+        for i in 0..satellites {
+            // spread around the planet so neighbouring orbits don't start lined up
+            let direction = Vec2::from_angle(i as f32 * 2.4);
+            let distance = planet_radius * (3.0 + 1.5 * i as f32);
+            let shape = Collider::Circle {
+                offset: Vec2::ZERO,
+                radius: 0.5,
+            };
+            let object = ObjectBuilder::new()
+                .with_body(
+                    RigidBody2DBuilder::new()
+                        .with_position(center + direction * distance)
+                        .with_vel(direction.perp() * gravity.orbital_speed(distance))
+                        .with_shape(shape.clone())
+                        .build(),
+                )
+                .with_collider(shape)
+                .with_color(DARKBLUE)
+                .with_name(format!("satellite_{i}"))
+                .build();
+            self.add(object);
+        }
+        planet
+    }
+
+    /// Spawns a grid of small circles that are connected to their neighbours by distance joints,
+    /// including the diagonal ones so the grid doesn't shear. Lower stiffness makes it more
+    /// jelly-like. The handles are returned row by row, starting at `origin` and going along +x
+    /// for the columns and against `up` for the rows, so the corners are easy to find for pinning.
+    pub fn spawn_soft_grid(
+        &mut self,
+        origin: Vec2,
+        rows: usize,
+        cols: usize,
+        spacing: f32,
+        stiffness: f32,
+    ) -> Vec<ObjectHandle> {
+        let shape = Collider::Circle {
+            offset: Vec2::ZERO,
+            radius: spacing * 0.25,
+        };
+        let down = -self.up();
+        let right = down.perp();
+
+        let mut handles = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                let position =
+                    origin + right * (col as f32 * spacing) + down * (row as f32 * spacing);
+                let object = ObjectBuilder::new()
+                    .with_body(
+                        RigidBody2DBuilder::new()
+                            .with_position(position)
+                            .with_shape(shape.clone())
+                            .build(),
+                    )
+                    .with_collider(shape.clone())
+                    .with_color(DARKBLUE)
+                    .with_name(format!("soft_grid_{row}_{col}"))
+                    .build();
+                handles.push(self.add(object));
+            }
+        }
+
+        let at = |row: usize, col: usize| handles[row * cols + col];
+        let mut connections = vec![];
+        for row in 0..rows {
+            for col in 0..cols {
+                if col + 1 < cols {
+                    connections.push((at(row, col), at(row, col + 1)));
+                }
+                if row + 1 < rows {
+                    connections.push((at(row, col), at(row + 1, col)));
+                }
+                if row + 1 < rows && col + 1 < cols {
+                    connections.push((at(row, col), at(row + 1, col + 1)));
+                    connections.push((at(row, col + 1), at(row + 1, col)));
+                }
+            }
+        }
+        for (a, b) in connections {
+            self.add_distance_joint(a, b, stiffness);
+        }
+
+        handles
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    pub fn objects_mut(&mut self) -> &mut [Object] {
+        &mut self.objects
+    }
+
+    pub fn get(&self, handle: ObjectHandle) -> Option<&Object> {
+        self.index_of(handle).map(|index| &self.objects[index])
+    }
+
+    pub fn get_mut(&mut self, handle: ObjectHandle) -> Option<&mut Object> {
+        self.index_of(handle).map(|index| &mut self.objects[index])
+    }
+
+    /// Enables or disables an object without removing it from the world.
+    /// Returns false if the handle doesn't belong to an object of this world.
+    pub fn set_enabled(&mut self, handle: ObjectHandle, enabled: bool) -> bool {
+        let Some(object) = self.get_mut(handle) else {
+            return false;
+        };
+        object.enabled = enabled;
+        true
+    }
+
+    /// the handle of the object at the given index of `objects()`
+    pub fn handle_of(&self, index: usize) -> ObjectHandle {
+        self.handles[index]
+    }
+
+    pub fn index_of(&self, handle: ObjectHandle) -> Option<usize> {
+        self.handles.iter().position(|h| *h == handle)
+    }
+
+    /// Returns every object whose collider bounds overlap the given region.
+    pub fn query_aabb(&self, min: Vec2, max: Vec2) -> Vec<ObjectHandle> {
+        let mut result = vec![];
+        for (index, object) in self.objects.iter().enumerate() {
+            let (Some(collider), Some(body)) = (&object.collider, &object.body) else {
+                continue;
+            };
+            if !object.enabled {
+                continue;
+            }
+            let (object_min, object_max) = collider.world_bounds(body.position);
+            if object_max.x >= min.x
+                && max.x >= object_min.x
+                && object_max.y >= min.y
+                && max.y >= object_min.y
+            {
+                result.push(self.handles[index]);
+            }
+        }
+        result
+    }
+
+    /// Returns the union of the bounds of all objects, or None if there are none.
+    pub fn world_aabb(&self) -> Option<(Vec2, Vec2)> {
+        self.objects
+            .iter()
+            .filter(|object| object.enabled)
+            .filter_map(|object| {
+                let (Some(collider), Some(body)) = (&object.collider, &object.body) else {
+                    return None;
+                };
+                Some(collider.world_bounds(body.position))
+            })
+            .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)))
+    }
+
+    /// The summed linear momentum of all dynamic bodies. Static and infinitely heavy bodies
+    /// are left out, so without external forces this stays constant through collisions.
+    pub fn total_momentum(&self) -> Vec2 {
+        self.objects
+            .iter()
+            .filter(|object| object.enabled)
+            .filter_map(|object| object.body.as_ref())
+            .filter_map(|body| Some(body.mass()? * body.vel))
+            .sum()
+    }
+
+    /// The mass weighted average position of all dynamic bodies, zero if there are none.
+    pub fn center_of_mass(&self) -> Vec2 {
+        let (weighted_sum, total_mass) = self
+            .objects
+            .iter()
+            .filter(|object| object.enabled)
+            .filter_map(|object| object.body.as_ref())
+            .filter_map(|body| Some((body.center_of_mass(), body.mass()?)))
+            .fold((Vec2::ZERO, 0.0), |(sum, total), (position, mass)| {
+                (sum + mass * position, total + mass)
+            });
+        if total_mass == 0.0 {
+            return Vec2::ZERO;
+        }
+        weighted_sum / total_mass
+    }
+
+    /// Computes the impulses the solver would apply to the contact in a single pass, without
+    /// changing any body. Returns the normal impulse and the friction impulse that body b would
+    /// receive, body a receives them negated. Both are zero if the contact doesn't refer to two
+    /// bodies of this world.
+    pub fn preview_contact_impulse(&self, contact: &Contact) -> (Vec2, Vec2) {
+        let body = |index: usize| self.objects.get(index)?.body.as_ref();
+        let (Some(body_a), Some(body_b)) = (body(contact.body_a_index), body(contact.body_b_index))
+        else {
+            return (Vec2::ZERO, Vec2::ZERO);
+        };
+        let restitution = self.restitution_override.as_ref().and_then(|callback| {
+            callback(
+                &self.objects[contact.body_a_index],
+                &self.objects[contact.body_b_index],
+            )
+        });
+        let params = ContactParams {
+            dt: self.fixed_dt,
+            use_bias: self.position_iterations == 0 && self.is_correcting(contact),
+            restitution_override: restitution,
+            restitution_threshold: self.restitution_threshold,
+            gravity_approach_speed: self.gravity_approach_speed(contact, self.fixed_dt),
+            ..Default::default()
+        };
+        let (p_n, p_t) = compute_contact_impulse(body_a, body_b, contact, &params);
+        (p_n * contact.normal, p_t * contact.normal.perp())
+    }
+
+    /// The contact of the last step that penetrated the deepest, useful to find the pair
+    /// that is responsible when the simulation becomes unstable.
+    pub fn deepest_contact(&self) -> Option<Contact> {
+        self.contacts
+            .iter()
+            .max_by(|a, b| a.pen_depth.total_cmp(&b.pen_depth))
+            .cloned()
+    }
+
+    /// Every object the given one touched during the last step, with the normal of the contact
+    /// pointing away from the given object. The object something rests on has a normal
+    /// pointing against `up()`.
+    pub fn contacts_for(&self, handle: ObjectHandle) -> Vec<(ObjectHandle, Vec2)> {
+        let Some(index) = self.index_of(handle) else {
+            return vec![];
+        };
+        self.contacts
+            .iter()
+            .filter_map(|contact| {
+                if contact.body_a_index == index {
+                    Some((self.handles[contact.body_b_index], contact.normal))
+                } else if contact.body_b_index == index {
+                    Some((self.handles[contact.body_a_index], -contact.normal))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns every pair of objects whose colliders touch, sensors included.
+    /// Nothing is resolved, so this can be used to check a level before simulating it.
+    pub fn overlapping_pairs(&self) -> Vec<(ObjectHandle, ObjectHandle)> {
+        let mut contacts = vec![];
+        check_collision(&self.objects, &self.name_filter, &mut contacts);
+        contacts
+            .iter()
+            .map(|contact| self.handle_pair(contact))
+            .collect()
+    }
+
+    /// Returns every object whose collider contains the given point.
+    pub fn query_point(&self, point: Vec2) -> Vec<ObjectHandle> {
+        let mut result = vec![];
+        for (index, object) in self.objects.iter().enumerate() {
+            let (Some(collider), Some(body)) = (&object.collider, &object.body) else {
+                continue;
+            };
+            if object.enabled && collider.contains_point(body, point) {
+                result.push(self.handles[index]);
+            }
+        }
+        result
+    }
+
+    /// Applies a force to every object in the region. Unlike an impulse the force only acts
+    /// during the next step, so it has to be applied every frame for a continuous effect.
+    pub fn apply_force_in_region(&mut self, min: Vec2, max: Vec2, force: Vec2) {
+        for handle in self.query_aabb(min, max) {
+            let Some(body) = self.get_mut(handle).and_then(|object| object.body.as_mut()) else {
+                continue;
+            };
+            if !body.is_static {
+                body.apply_force(force);
+            }
+        }
+    }
+
+    /// Applies a force pointing away from `center` to every object whose position lies within `radius`.
+    /// A negative strength pulls the objects towards the center instead.
+    pub fn apply_force_radial(&mut self, center: Vec2, radius: f32, strength: f32) {
+        let extent = Vec2::splat(radius);
+        for handle in self.query_aabb(center - extent, center + extent) {
+            let Some(body) = self.get_mut(handle).and_then(|object| object.body.as_mut()) else {
+                continue;
+            };
+            let offset = body.position - center;
+            if body.is_static || offset.length() > radius {
+                continue;
+            }
+            body.apply_force(offset.normalize_or_zero() * strength);
+        }
+    }
+
+    pub fn collision_events(&self) -> &[CollisionEvent] {
+        &self.collision_events
+    }
+
+    /// One line per collision of the last step, like `"circle <-> floor, depth 0.03, impulse 4.2"`.
+    /// Empty unless `log_collisions` is set.
+    pub fn collision_log(&self) -> &[String] {
+        &self.collision_log
+    }
+
+    /// Returns the handles of all bodies whose state became NaN or infinite.
+    pub fn validate(&self) -> Result<(), Vec<ObjectHandle>> {
+        let invalid: Vec<ObjectHandle> = self
+            .objects
+            .iter()
+            .zip(&self.handles)
+            .filter(|(object, _)| object.body.as_ref().is_some_and(|body| !body.is_finite()))
+            .map(|(_, handle)| *handle)
+            .collect();
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid)
+        }
+    }
+
+    /// The bodies that were found to be invalid at the end of the last step.
+    /// Always empty unless `validate_every_step` is set.
+    pub fn invalid_bodies(&self) -> &[ObjectHandle] {
+        &self.invalid_bodies
+    }
+
+    pub fn step_stats(&self) -> StepStats {
+        self.step_stats
+    }
+
+    /// the sensor overlaps that started or ended during the last step
+    pub fn sensor_events(&self) -> &[SensorEvent] {
+        &self.sensor_events
+    }
+
+    /// the pairs that were separated during the last step because they penetrated deeper than
+    /// `max_penetration`
+    pub fn emergency_separations(&self) -> &[EmergencySeparation] {
+        &self.emergency_separations
+    }
+
+    /// The direction opposite to the gravity, or `UP` if there is no gravity.
+    /// Use this instead of `UP` for anything that should follow the gravity, like "on the ground".
+    pub fn up(&self) -> Vec2 {
+        (-self.gravity).try_normalize().unwrap_or(UP)
+    }
+
+    /// Sets the gravity to point in `direction` (doesn't have to be normalized) with the given magnitude.
+    pub fn set_gravity_direction_and_magnitude(&mut self, direction: Vec2, magnitude: f32) {
+        self.gravity = direction.normalize_or_zero() * magnitude;
+    }
+
+    /// Registers a callback that is consulted for every contact before it is resolved.
+    /// Returning false drops the contact, so the two objects pass through each other.
+    /// It is called once per touching pair and step, even if the contacts are detected again
+    /// later in the step, and the answer holds for the rest of the step.
+    pub fn set_pre_solve<F>(&mut self, callback: F)
+    where
+        F: Fn(&Contact, &Object, &Object) -> bool + 'static,
+    {
+        self.pre_solve = Some(Box::new(callback));
+    }
+
+    pub fn clear_pre_solve(&mut self) {
+        self.pre_solve = None;
+    }
+
+    /// Starts recording the state of every body after each step. Clears the previous recording.
+    pub fn start_recording(&mut self) {
+        self.recorded_frames.clear();
+        self.is_recording = true;
+    }
+
+    /// Stops recording but keeps the recorded frames so that they can be played back.
+    pub fn stop_recording(&mut self) {
+        self.is_recording = false;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.is_recording
+    }
+
+    pub fn recorded_frame_count(&self) -> usize {
+        self.recorded_frames.len()
+    }
+
+    /// Restores the bodies to the state they had in the given recorded frame. Objects that were
+    /// removed since are skipped and objects that were added later keep their current state.
+    /// Returns false if no such frame was recorded.
+    pub fn play(&mut self, frame: usize) -> bool {
+        let Some(states) = self.recorded_frames.get(frame) else {
+            return false;
+        };
+        for &(handle, state) in states {
+            let Some(index) = self.index_of(handle) else {
+                continue;
+            };
+            if let Some(body) = &mut self.objects[index].body {
+                body.set_state(state);
+            }
+        }
+        true
+    }
+
+    /// Registers a callback that can replace the combined restitution of a colliding pair.
+    /// Returning None keeps the restitution of the bodies.
+    pub fn set_restitution_override<F>(&mut self, callback: F)
+    where
+        F: Fn(&Object, &Object) -> Option<f32> + 'static,
+    {
+        self.restitution_override = Some(Box::new(callback));
+    }
+
+    pub fn clear_restitution_override(&mut self) {
+        self.restitution_override = None;
+    }
+
+    /// Advances the simulation by the frame time in steps of `fixed_dt`.
+    /// The time that is left over is carried over to the next call, see `alpha`.
+    pub fn update(&mut self, frame_time: f32) {
+        // cap the frame time so that a long frame doesn't cause a spiral of death
+        self.accumulator += frame_time.min(0.25);
+        while self.accumulator >= self.fixed_dt {
+            self.step(self.fixed_dt);
+            self.accumulator -= self.fixed_dt;
+        }
+    }
+
+    /// Runs `n` steps of `dt` seconds, like calling `step` in a loop.
+    /// Returns the stats of all steps added up, with the largest residual of all steps and
+    /// `converged` only if every step converged. `step_stats` still holds the ones of the last step.
+    pub fn step_n(&mut self, dt: f32, n: usize) -> StepStats {
+        let mut total = StepStats {
+            converged: true,
+            ..Default::default()
+        };
+        for _ in 0..n {
+            self.step(dt);
+            total.island_count += self.step_stats.island_count;
+            total.pair_tests += self.step_stats.pair_tests;
+            total.contact_count += self.step_stats.contact_count;
+            total.contact_solves += self.step_stats.contact_solves;
+            total.residual = total.residual.max(self.step_stats.residual);
+            total.converged &= self.step_stats.converged;
+        }
+        total
+    }
+
+    /// How far the leftover time is into the next step, in [0, 1).
+    /// Use it to interpolate between the previous and the current state when rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.fixed_dt
+    }
+
+    /// Draws every object at its interpolated position, collecting all outlines
+    /// into a single batch so they go out in as few draw calls as possible.
+    pub fn draw(&self, camera: &Camera) {
+        let alpha = self.alpha();
+        let mut batch = LineBatch::new();
+        for object in self.objects.iter().filter(|object| object.enabled) {
+            if let Some((position, _)) = object.interpolated_transform(alpha) {
+                object.add_outline(camera, position, &mut batch);
+            }
+        }
+        batch.flush();
+    }
+
+    /// advances the simulation by dt seconds
+    pub fn step(&mut self, dt: f32) {
+        self.contacts.clear();
+        self.collision_events.clear();
+        self.pre_solve_answers.clear();
+
+        for object in self.objects.iter_mut() {
+            let Some(body) = &mut object.body else {
+                continue;
+            };
+            body.prev_position = body.position;
+            body.prev_angle = body.angle;
+        }
+
+        self.apply_point_gravities();
+        if self.gravity_sequencing == GravitySequencing::BeforeSolve {
+            for object in self.objects.iter_mut().filter(|object| object.enabled) {
+                if let Some(body) = &mut object.body {
+                    body.apply_gravity(dt, self.gravity);
+                }
+            }
+        }
+
+        let mut contacts = std::mem::take(&mut self.contacts);
+        let pair_tests = self.detect_contacts(&mut contacts, dt);
+        self.update_correcting_pairs(&contacts);
+        self.separate_deep_contacts(&mut contacts);
+
+        // the impulses each pair of objects received so far during this step
+        let mut accumulated_impulses: HashMap<(usize, usize), AccumulatedImpulse> = HashMap::new();
+        // the energy of each pair before it was resolved for the first time in this step
+        let mut kinetic_energy_before: HashMap<(usize, usize), f32> = HashMap::new();
+        let joint_pairs: Vec<Option<(usize, usize)>> = self
+            .joints
+            .iter()
+            .map(|joint| Some((self.index_of(joint.a)?, self.index_of(joint.b)?)))
+            .collect();
+        let islands = build_islands(&self.objects, &contacts, &joint_pairs);
+        self.step_stats = StepStats {
+            island_count: islands.len(),
+            pair_tests,
+            contact_count: contacts.len(),
+            contact_solves: 0,
+            residual: 0.0,
+            converged: true,
+        };
+
+        if self.detect_every_iteration {
+            // the contacts change between the iterations, so the islands of the first set
+            // don't fit the later ones and everything is solved together
+            for iteration in 0..self.velocity_iterations {
+                if iteration > 0 {
+                    for contact in contacts.iter() {
+                        self.record_collision_event(contact, &kinetic_energy_before);
+                    }
+                    self.detect_contacts(&mut contacts, dt);
+                }
+                for contact in contacts.iter_mut() {
+                    self.solve_contact(
+                        contact,
+                        dt,
+                        &mut accumulated_impulses,
+                        &mut kinetic_energy_before,
+                    );
+                    self.step_stats.contact_solves += 1;
+                }
+            }
+            // joints are cheap compared to the collision detection, so they get their own passes
+            for _ in 0..self.joint_iterations {
+                for i in 0..self.joints.len() {
+                    self.solve_joint(i, dt);
+                }
+            }
+        } else {
+            let contact_iterations = self.contact_iterations(&contacts);
+            for island in &islands {
+                let island_iterations = island
+                    .contacts
+                    .iter()
+                    .map(|&i| contact_iterations[i])
+                    .max()
+                    .unwrap_or(0);
+                for iteration in 0..island_iterations {
+                    for &i in &island.contacts {
+                        if iteration >= contact_iterations[i] {
+                            continue;
+                        }
+                        self.solve_contact(
+                            &mut contacts[i],
+                            dt,
+                            &mut accumulated_impulses,
+                            &mut kinetic_energy_before,
+                        );
+                        self.step_stats.contact_solves += 1;
+                    }
+                }
+                for _ in 0..self.joint_iterations {
+                    for &i in &island.joints {
+                        self.solve_joint(i, dt);
+                    }
+                }
+            }
+        }
+        let residual = self.contact_residual(&contacts);
+        self.step_stats.residual = residual;
+        self.step_stats.converged = residual <= self.convergence_tolerance;
+        for contact in contacts.iter() {
+            self.record_collision_event(contact, &kinetic_energy_before);
+        }
+        self.contacts = contacts;
+        self.update_collision_log();
+
+        if self.resting_angular_damping > 0.0 {
+            self.damp_resting_rotation(dt);
+        }
+        if self.contact_linear_damping > 0.0 || self.contact_angular_damping > 0.0 {
+            self.damp_bodies_in_contact(dt);
+        }
+
+        let impact_fractions = match self.ccd_speed_threshold {
+            Some(threshold) => self.find_fast_impacts(threshold, dt),
+            None => vec![],
+        };
+
+        for object in self.objects.iter_mut().filter(|object| object.enabled) {
+            let Some(body) = &mut object.body else {
+                continue;
+            };
+            match self.gravity_sequencing {
+                GravitySequencing::AfterSolve => body.update(dt, self.gravity),
+                GravitySequencing::BeforeSolve => body.integrate(dt, Vec2::ZERO),
+            }
+        }
+
+        // fast bodies only move up to the point where they hit something, the discrete
+        // collision detection of the next step takes over from there
+        for (index, fraction) in impact_fractions {
+            let Some(body) = &mut self.objects[index].body else {
+                continue;
+            };
+            body.position = body.prev_position + (body.position - body.prev_position) * fraction;
+        }
+
+        if self.position_iterations > 0 {
+            self.correct_positions();
+        }
+
+        if let Some((min, max)) = self.wrap {
+            self.wrap_positions(min, max);
+        }
+        self.update_sensor_events();
+
+        self.invalid_bodies.clear();
+        if self.validate_every_step
+            && let Err(invalid) = self.validate()
+        {
+            if self.reset_invalid_bodies {
+                for &handle in &invalid {
+                    if let Some(body) = self.get_mut(handle).and_then(|object| object.body.as_mut())
+                    {
+                        body.reset_to_safe_state();
+                    }
+                }
+            }
+            self.invalid_bodies = invalid;
+        }
+
+        if self.is_recording {
+            let states = self
+                .objects
+                .iter()
+                .zip(&self.handles)
+                .filter_map(|(object, handle)| Some((*handle, object.body.as_ref()?.state())))
+                .collect();
+            self.recorded_frames.push(states);
+        }
+    }
+
+    /// Fills `contacts` with the contacts the solver should resolve, limited to the contact budget.
+    /// Returns the number of pairs that went through the narrow phase.
+    fn detect_contacts(&mut self, contacts: &mut Vec<Contact>, dt: f32) -> usize {
+        let pair_tests = check_collision(&self.objects, &self.name_filter, contacts);
+        // sensor and vetoed contacts must not use up the budget
+        contacts.retain(|contact| self.should_resolve(contact));
+        if self.deterministic {
+            // also decides which of the equally urgent contacts stay within the budget
+            self.order_deterministically(contacts);
+        }
+        if let Some(budget) = self.contact_budget {
+            self.prioritize_contacts(contacts, dt);
+            contacts.truncate(budget);
+            if self.deterministic {
+                self.order_deterministically(contacts);
+            }
+        }
+        pair_tests
+    }
+
+    /// The position iterations. They only correct the pairs the velocity iterations resolved,
+    /// the contacts over the budget keep their penetration until a later step picks them up.
+    /// Sensor and vetoed contacts aren't in that list either, so they are skipped as well.
+    fn correct_positions(&mut self) {
+        let resolved_pairs: HashSet<(ObjectHandle, ObjectHandle)> = self
+            .contacts
+            .iter()
+            .map(|contact| self.pair_key(contact))
+            .collect();
+        let mut contacts = std::mem::take(&mut self.scratch_contacts);
+        for _ in 0..self.position_iterations {
+            check_collision(&self.objects, &self.name_filter, &mut contacts);
+            if self.deterministic {
+                self.order_deterministically(&mut contacts);
+            }
+            for contact in contacts.iter() {
+                let pair = self.pair_key(contact);
+                if !resolved_pairs.contains(&pair) || !self.correcting_pairs.contains(&pair) {
+                    continue;
+                }
+                if contact.pen_depth <= SLOP + CORRECTION_STOP_EPSILON {
+                    continue;
+                }
+                correct_penetration(&mut self.objects, contact);
+            }
+        }
+        self.scratch_contacts = contacts;
+    }
+
+    /// Sweeps every body that is faster than `threshold` against all other objects and returns
+    /// the indices of the ones that would hit something during this step, together with the
+    /// fraction of the step at which they do. Objects that already touch at the start of the step
+    /// are left to the discrete collision detection.
+    fn find_fast_impacts(&self, threshold: f32, dt: f32) -> Vec<(usize, f32)> {
+        let mut impacts = vec![];
+        for (i, object) in self.objects.iter().enumerate() {
+            let (Some(body), Some(collider)) = (&object.body, &object.collider) else {
+                continue;
+            };
+            if body.is_static
+                || object.is_sensor
+                || !object.enabled
+                || body.vel.length() <= threshold
+            {
+                continue;
+            }
+
+            let earliest = self
+                .objects
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| {
+                    *j != i
+                        && !other.is_sensor
+                        && other.enabled
+                        && self.name_filter.allows(&object.name, &other.name)
+                })
+                .filter_map(|(_, other)| {
+                    let (Some(other_body), Some(other_collider)) = (&other.body, &other.collider)
+                    else {
+                        return None;
+                    };
+                    if collider.distance(body, other_collider, other_body) <= 0.0 {
+                        return None;
+                    }
+                    toi(collider, body, other_collider, other_body, dt)
+                })
+                .reduce(f32::min);
+            if let Some(fraction) = earliest {
+                impacts.push((i, fraction));
+            }
+        }
+        impacts
+    }
+
+    fn apply_point_gravities(&mut self) {
+        for i in 0..self.point_gravities.len() {
+            let gravity = self.point_gravities[i];
+            let Some(attractor_index) = self.index_of(gravity.attractor) else {
+                continue;
+            };
+            let Some(center) = self.objects[attractor_index]
+                .body
+                .as_ref()
+                .map(|body| body.center_of_mass())
+            else {
+                continue;
+            };
+            for (index, object) in self.objects.iter_mut().enumerate() {
+                if index == attractor_index || !object.enabled {
+                    continue;
+                }
+                let Some(body) = &mut object.body else {
+                    continue;
+                };
+                let force = gravity.force_on(center, body);
+                body.apply_force(force);
+            }
+        }
+    }
+
+    fn wrap_positions(&mut self, min: Vec2, max: Vec2) {
+        let size = max - min;
+        let wrap_axis = |value: f32, min: f32, size: f32| {
+            if size > 0.0 {
+                min + (value - min).rem_euclid(size)
+            } else {
+                value
+            }
+        };
+        for object in self.objects.iter_mut().filter(|object| object.enabled) {
+            let Some(body) = &mut object.body else {
+                continue;
+            };
+            if body.is_static {
+                continue;
+            }
+            let wrapped = vec2(
+                wrap_axis(body.position.x, min.x, size.x),
+                wrap_axis(body.position.y, min.y, size.y),
+            );
+            // move the previous position along so the interpolation doesn't sweep across the world
+            body.prev_position += wrapped - body.position;
+            body.position = wrapped;
+        }
+    }
+
+    /// The number of velocity iterations every contact gets in this step, see `iteration_lod`.
+    fn contact_iterations(&self, contacts: &[Contact]) -> Vec<usize> {
+        contacts
+            .iter()
+            .map(|contact| {
+                let Some(lod) = self.iteration_lod else {
+                    return self.velocity_iterations;
+                };
+                let body = |index: usize| self.objects[index].body.as_ref();
+                let (Some(body_a), Some(body_b)) =
+                    (body(contact.body_a_index), body(contact.body_b_index))
+                else {
+                    return lod.min_iterations;
+                };
+                let speed = contact_relative_velocity(body_a, body_b, contact).length();
+                if speed <= self.resting_velocity_threshold {
+                    return lod.min_iterations;
+                }
+                lod.iterations_for_speed(speed)
+            })
+            .collect()
+    }
+
+    /// Runs one solver iteration on the contact, keeping track of the impulses and the energy of
+    /// the pair over the whole step.
+    fn solve_contact(
+        &mut self,
+        contact: &mut Contact,
+        dt: f32,
+        accumulated_impulses: &mut HashMap<(usize, usize), AccumulatedImpulse>,
+        kinetic_energy_before: &mut HashMap<(usize, usize), f32>,
+    ) {
+        let pair = (contact.body_a_index, contact.body_b_index);
+        let restitution = self.restitution_override.as_ref().and_then(|callback| {
+            callback(
+                &self.objects[contact.body_a_index],
+                &self.objects[contact.body_b_index],
+            )
+        });
+        kinetic_energy_before
+            .entry(pair)
+            .or_insert_with(|| self.pair_kinetic_energy(contact));
+        let params = ContactParams {
+            dt,
+            use_bias: self.position_iterations == 0 && self.is_correcting(contact),
+            restitution_override: restitution,
+            restitution_threshold: self.restitution_threshold,
+            gravity_approach_speed: self.gravity_approach_speed(contact, dt),
+            ..Default::default()
+        };
+        let accumulated = accumulated_impulses.entry(pair).or_default();
+        resolve_interpenetration(&mut self.objects, contact, params, accumulated);
+    }
+
+    /// The fastest approach along the normal of any of the contacts, 0 if they all separate or rest.
+    fn contact_residual(&self, contacts: &[Contact]) -> f32 {
+        contacts
+            .iter()
+            .filter_map(|contact| {
+                let body_a = self.objects[contact.body_a_index].body.as_ref()?;
+                let body_b = self.objects[contact.body_b_index].body.as_ref()?;
+                let v_n = contact_relative_velocity(body_a, body_b, contact).dot(contact.normal);
+                Some(-v_n)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    fn solve_joint(&mut self, joint_index: usize, dt: f32) {
+        let joint = self.joints[joint_index];
+        let (Some(index_a), Some(index_b)) = (self.index_of(joint.a), self.index_of(joint.b))
+        else {
+            return;
+        };
+        if index_a == index_b {
+            return;
+        }
+        let (a, b) = get_two_mut(&mut self.objects, index_a, index_b);
+        if !a.enabled || !b.enabled {
+            return;
+        }
+        let (Some(body_a), Some(body_b)) = (&mut a.body, &mut b.body) else {
+            return;
+        };
+        joint.solve(body_a, body_b, dt);
+    }
+
+    /// Decides which contacts get their penetration corrected in this step. A pair that was not
+    /// corrected in the last step only starts once it is clearly deeper than the slop, and one that
+    /// was keeps being corrected until it is almost exactly at the slop. That way resting contacts
+    /// don't flicker between corrected and not because of floating point noise.
+    fn update_correcting_pairs(&mut self, contacts: &[Contact]) {
+        let mut correcting_pairs = HashSet::new();
+        for contact in contacts {
+            let pair = self.pair_key(contact);
+            let threshold = if self.correcting_pairs.contains(&pair) {
+                SLOP + CORRECTION_STOP_EPSILON
+            } else {
+                SLOP + CORRECTION_START_EPSILON
+            };
+            if contact.pen_depth > threshold {
+                correcting_pairs.insert(pair);
+            }
+        }
+        self.correcting_pairs = correcting_pairs;
+    }
+
+    fn is_correcting(&self, contact: &Contact) -> bool {
+        self.correcting_pairs.contains(&self.pair_key(contact))
+    }
+
+    fn handle_pair(&self, contact: &Contact) -> (ObjectHandle, ObjectHandle) {
+        (
+            self.handles[contact.body_a_index],
+            self.handles[contact.body_b_index],
+        )
+    }
+
+    /// the handles of the contact with the smaller one first, the same whichever body is a
+    fn pair_key(&self, contact: &Contact) -> (ObjectHandle, ObjectHandle) {
+        let (a, b) = self.handle_pair(contact);
+        (a.min(b), a.max(b))
+    }
+
+    /// Orders the contacts by where their bodies are, and makes the body that comes first body a
+    /// of its contact. None of it depends on where the objects are in the object list, only
+    /// bodies at the exact same position fall back to the order of their handles.
+    fn order_deterministically(&self, contacts: &mut [Contact]) {
+        let compare_bodies = |i: usize, j: usize| {
+            let position = |index: usize| {
+                self.objects[index]
+                    .body
+                    .as_ref()
+                    .map_or(Vec2::ZERO, |body| body.position)
+            };
+            let (p, q) = (position(i), position(j));
+            p.x.total_cmp(&q.x)
+                .then(p.y.total_cmp(&q.y))
+                .then(self.handles[i].cmp(&self.handles[j]))
+        };
+        for contact in contacts.iter_mut() {
+            let (a, b) = (contact.body_a_index, contact.body_b_index);
+            if compare_bodies(a, b).is_le() {
+                continue;
+            }
+            // the narrow phase doesn't give exactly the same result for both orders
+            let (object_a, object_b) = (&self.objects[a], &self.objects[b]);
+            let (Some(collider_a), Some(body_a), Some(collider_b), Some(body_b)) = (
+                &object_a.collider,
+                &object_a.body,
+                &object_b.collider,
+                &object_b.body,
+            ) else {
+                continue;
+            };
+            if let Some(swapped) = collider_b.collides_with(body_b, body_a, collider_a, b, a) {
+                *contact = swapped;
+            }
+        }
+        contacts.sort_by(|x, y| {
+            compare_bodies(x.body_a_index, y.body_a_index)
+                .then(compare_bodies(x.body_b_index, y.body_b_index))
+        });
+    }
+
+    /// Sorts the contacts so that the deepest and fastest approaching ones come first.
+    fn prioritize_contacts(&self, contacts: &mut [Contact], dt: f32) {
+        // how deep the bodies will be inside of each other after this step if nothing is done
+        let urgency = |contact: &Contact| {
+            let (Some(body_a), Some(body_b)) = (
+                &self.objects[contact.body_a_index].body,
+                &self.objects[contact.body_b_index].body,
+            ) else {
+                return contact.pen_depth;
+            };
+            let v_n = contact_relative_velocity(body_a, body_b, contact).dot(contact.normal);
+            contact.pen_depth + f32::max(-v_n, 0.0) * dt
+        };
+        contacts.sort_by(|a, b| urgency(b).total_cmp(&urgency(a)));
+    }
+
+    /// false for sensor contacts and contacts the pre-solve callback rejected
+    fn should_resolve(&mut self, contact: &Contact) -> bool {
+        let a = &self.objects[contact.body_a_index];
+        let b = &self.objects[contact.body_b_index];
+        if a.is_sensor || b.is_sensor {
+            return false;
+        }
+        let Some(pre_solve) = &self.pre_solve else {
+            return true;
+        };
+        let pair = self.pair_key(contact);
+        if let Some(&answer) = self.pre_solve_answers.get(&pair) {
+            return answer;
+        }
+        let answer = pre_solve(contact, a, b);
+        self.pre_solve_answers.insert(pair, answer);
+        answer
+    }
+
+    /// Pulls the bodies of every contact deeper than `max_penetration` apart until they only
+    /// touch. The contacts stay, so the solver still stops the bodies from approaching.
+    /// `contacts` comes from `detect_contacts`, so sensor and vetoed contacts are already gone.
+    fn separate_deep_contacts(&mut self, contacts: &mut [Contact]) {
+        self.emergency_separations.clear();
+        let Some(max_penetration) = self.max_penetration else {
+            return;
+        };
+        for contact in contacts.iter_mut() {
+            if contact.pen_depth <= max_penetration {
+                continue;
+            }
+            move_apart(&mut self.objects, contact, contact.pen_depth - SLOP);
+            let (a, b) = self.handle_pair(contact);
+            self.emergency_separations.push(EmergencySeparation {
+                a,
+                b,
+                pen_depth: contact.pen_depth,
+            });
+            contact.pen_depth = SLOP;
+        }
+    }
+
+    /// Compares the current sensor overlaps with the ones of the last step.
+    fn update_sensor_events(&mut self) {
+        self.sensor_events.clear();
+
+        let mut overlaps = HashSet::new();
+        check_collision(&self.objects, &self.name_filter, &mut self.scratch_contacts);
+        for contact in self.scratch_contacts.iter() {
+            let a = &self.objects[contact.body_a_index];
+            let b = &self.objects[contact.body_b_index];
+            if !a.is_sensor && !b.is_sensor {
+                continue;
+            }
+            let handle_a = self.handles[contact.body_a_index];
+            let handle_b = self.handles[contact.body_b_index];
+            overlaps.insert((handle_a.min(handle_b), handle_a.max(handle_b)));
+        }
+
+        for (a, b) in overlaps.difference(&self.sensor_overlaps) {
+            self.sensor_events.push(SensorEvent::Enter(*a, *b));
+        }
+        for (a, b) in self.sensor_overlaps.difference(&overlaps) {
+            self.sensor_events.push(SensorEvent::Exit(*a, *b));
+        }
+        self.sensor_overlaps = overlaps;
+    }
+
+    /// Bleeds off the spin of bodies that have a resting contact, which single point
+    /// contacts tend to introduce on boxes lying on the ground.
+    fn damp_resting_rotation(&mut self, dt: f32) {
+        let mut is_resting = vec![false; self.objects.len()];
+        for contact in self.contacts.iter() {
+            let (Some(body_a), Some(body_b)) = (
+                &self.objects[contact.body_a_index].body,
+                &self.objects[contact.body_b_index].body,
+            ) else {
+                continue;
+            };
+            // a body that doesn't move but spins is not resting
+            let relative_vel = contact_relative_velocity(body_a, body_b, contact);
+            if relative_vel.length() < self.resting_velocity_threshold {
+                is_resting[contact.body_a_index] = true;
+                is_resting[contact.body_b_index] = true;
+            }
+        }
+
+        let damping = 1.0 / (1.0 + dt * self.resting_angular_damping);
+        for (object, is_resting) in self.objects.iter_mut().zip(is_resting) {
+            let Some(body) = &mut object.body else {
+                continue;
+            };
+            if is_resting {
+                body.angular_vel *= damping;
+            }
+        }
+    }
+
+    /// Damps the bodies that had a contact in this step, like the energy a real collision
+    /// loses beyond what the restitution accounts for.
+    fn damp_bodies_in_contact(&mut self, dt: f32) {
+        let mut in_contact = vec![false; self.objects.len()];
+        for contact in self.contacts.iter() {
+            in_contact[contact.body_a_index] = true;
+            in_contact[contact.body_b_index] = true;
+        }
+
+        let linear_damping = 1.0 / (1.0 + dt * self.contact_linear_damping);
+        let angular_damping = 1.0 / (1.0 + dt * self.contact_angular_damping);
+        for (object, in_contact) in self.objects.iter_mut().zip(in_contact) {
+            let Some(body) = &mut object.body else {
+                continue;
+            };
+            if in_contact && !body.is_static {
+                body.vel *= linear_damping;
+                body.angular_vel *= angular_damping;
+            }
+        }
+    }
+
+    /// How fast the gravity of a single step makes the two bodies of the contact approach each
+    /// other, 0 if it pulls them apart or `suppress_gravity_bounce` is off.
+    fn gravity_approach_speed(&self, contact: &Contact, dt: f32) -> f32 {
+        if !self.suppress_gravity_bounce {
+            return 0.0;
+        }
+        // static and infinitely heavy bodies aren't moved by the gravity, see `RigidBody2D::update`
+        let gravity_of = |index: usize| match &self.objects[index].body {
+            Some(body) if !body.is_static && body.inverse_mass > 0.0 => body.gravity(self.gravity),
+            _ => Vec2::ZERO,
+        };
+        let relative_gravity = gravity_of(contact.body_b_index) - gravity_of(contact.body_a_index);
+        f32::max(0.0, -relative_gravity.dot(contact.normal) * dt)
+    }
+
+    fn pair_kinetic_energy(&self, contact: &Contact) -> f32 {
+        [contact.body_a_index, contact.body_b_index]
+            .iter()
+            .filter_map(|index| self.objects[*index].body.as_ref())
+            .map(|body| body.kinetic_energy())
+            .sum()
+    }
+
+    fn update_collision_log(&mut self) {
+        self.collision_log.clear();
+        if !self.log_collisions {
+            return;
+        }
+        for event in &self.collision_events {
+            let name = |handle| self.get(handle).map_or("?", |object| object.name.as_str());
+            self.collision_log.push(format!(
+                "{} <-> {}, depth {:.2}, impulse {:.1}",
+                name(event.a),
+                name(event.b),
+                event.pen_depth,
+                event.normal_impulse
+            ));
+        }
+    }
+
+    fn record_collision_event(
+        &mut self,
+        contact: &Contact,
+        kinetic_energy_before: &HashMap<(usize, usize), f32>,
+    ) {
+        let a = self.handles[contact.body_a_index];
+        let b = self.handles[contact.body_b_index];
+        let kinetic_energy_after = self.pair_kinetic_energy(contact);
+
+        // a pair can only have one contact, its impulses are already summed up over the iterations
+        if let Some(event) = self
+            .collision_events
+            .iter_mut()
+            .find(|event| event.a == a && event.b == b)
+        {
+            event.approach_speed = event.approach_speed.max(contact.approach_speed);
+            event.pen_depth = event.pen_depth.max(contact.pen_depth);
+            event.normal_impulse = contact.normal_impulse;
+            event.kinetic_energy_after = kinetic_energy_after;
+            return;
+        }
+
+        let pair = (contact.body_a_index, contact.body_b_index);
+
+        self.collision_events.push(CollisionEvent {
+            a,
+            b,
+            point: contact.point,
+            normal: contact.normal,
+            pen_depth: contact.pen_depth,
+            approach_speed: contact.approach_speed,
+            normal_impulse: contact.normal_impulse,
+            kinetic_energy_before: kinetic_energy_before
+                .get(&pair)
+                .copied()
+                .unwrap_or(kinetic_energy_after),
+            kinetic_energy_after,
+        });
+    }
+}
+
+/// Borrows two different elements of a slice mutably at the same time, in whichever order the
+/// indices are given. Panics if `i == j` or if one of them is out of bounds.
+pub fn get_two_mut<T>(slice: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
+    assert_ne!(i, j, "get_two_mut needs two different indices");
+    if i < j {
+        // everything from j onwards is in the right half, so both can be borrowed at once
+        let (left, right) = slice.split_at_mut(j);
+        (&mut left[i], &mut right[0])
+    } else {
+        let (left, right) = slice.split_at_mut(i);
+        (&mut right[0], &mut left[j])
+    }
+}
+
+// slop is there to reduce jittering
+const SLOP: f32 = 0.01; // allow for 1 cm of slop
+
+// penetrations this close to the slop are left alone, and a pair that is at rest has to sink
+// deeper than the start epsilon before it is corrected again
+const CORRECTION_STOP_EPSILON: f32 = 0.001;
+const CORRECTION_START_EPSILON: f32 = 0.005;
+
+// this makes it so that the bodies don't drastically move apart but are rather gently moved
+// apart each frame
+const BIAS_FACTOR: f32 = 0.2;
+
+// a resting contact approaches with about the speed the gravity of one step adds,
+// this leaves some room for rounding and for the bias of the last step
+const GRAVITY_BOUNCE_TOLERANCE: f32 = 1.1;
+
+#[derive(Default)]
+struct AccumulatedImpulse {
+    normal: f32,
+    tangent: f32,
+}
+
+/// The velocity of body b relative to body a at the contact point, including the part
+/// that comes from the rotation of the bodies.
+pub fn contact_relative_velocity(
+    body_a: &RigidBody2D,
+    body_b: &RigidBody2D,
+    contact: &Contact,
+) -> Vec2 {
+    body_b.velocity_at_point(contact.point) - body_a.velocity_at_point(contact.point)
+}
+
+/// The coefficient of friction of the pair for sliding along `tangent`.
+fn combined_mu(body_a: &RigidBody2D, body_b: &RigidBody2D, tangent: Vec2) -> f32 {
+    body_a.mu_in_direction(tangent) * body_b.mu_in_direction(tangent)
+}
+
+/// Everything `compute_contact_impulse` needs besides the two bodies and the contact.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContactParams {
+    pub dt: f32,
+    // push penetrating bodies apart with a velocity bias (Baumgarte stabilization)
+    pub use_bias: bool,
+    // used instead of the product of the restitutions of the bodies
+    pub restitution_override: Option<f32>,
+    // contacts approaching slower than this don't bounce
+    pub restitution_threshold: f32,
+    // contacts approaching no faster than this don't bounce either, it is the speed the gravity
+    // of one step adds, see `World::suppress_gravity_bounce`
+    pub gravity_approach_speed: f32,
+    // the impulses this contact received earlier in the same step
+    pub accumulated_normal: f32,
+    pub accumulated_tangent: f32,
+}
+
+/// Computes the impulses that resolve the contact without applying them.
+/// Returns the magnitude of the normal impulse and of the friction impulse along `normal.perp()`.
+/// Body b receives them as they are and body a receives them negated.
+pub fn compute_contact_impulse(
+    body_a: &RigidBody2D,
+    body_b: &RigidBody2D,
+    contact: &Contact,
+    params: &ContactParams,
+) -> (f32, f32) {
+    let relative_vel = contact_relative_velocity(body_a, body_b, contact);
+    // according to documentation, .perp() rotates the vector clockwise by 90 degrees
+    let tangent = contact.normal.perp();
+
+    // tangent velocity
+    let v_t = relative_vel.dot(tangent);
+
+    // relative velocity along the normal
+    let v_n = relative_vel.dot(contact.normal);
+
+    let bias_vel = if params.use_bias {
+        (BIAS_FACTOR / params.dt) * f32::max(0.0, contact.pen_depth - SLOP)
+    } else {
+        0.0
+    };
+
+    // both impulses act at the contact point, away from the centers of mass, so part of them
+    // goes into spinning the bodies
+    let r_a = contact.point - body_a.center_of_mass();
+    let r_b = contact.point - body_b.center_of_mass();
+
+    // NOTE:
+    // this is quasi the effective mass
+    let k_n = body_a.inverse_mass
+        + body_b.inverse_mass
+        + r_a.perp_dot(contact.normal).powi(2) * body_a.inverse_inertia
+        + r_b.perp_dot(contact.normal).powi(2) * body_b.inverse_inertia;
+
+    // this is the effective mass for the friction calculation
+    // here we dot multiply with tangent vector instead of the normal vector.
+    let k_t = body_a.inverse_mass
+        + body_b.inverse_mass
+        + r_a.perp_dot(tangent).powi(2) * body_a.inverse_inertia
+        + r_b.perp_dot(tangent).powi(2) * body_b.inverse_inertia;
+
+    // neither body can be moved
+    if k_n == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    // magnitude of the impulse
+    // if the relative velocity is greater than zero, the bodies are already
+    // moving apart
+    // the fields and the override are not validated like the builder is, so clamp here as well
+    let is_gravity_only = -v_n <= params.gravity_approach_speed * GRAVITY_BOUNCE_TOLERANCE;
+    let restitution = if -v_n < params.restitution_threshold || is_gravity_only {
+        0.0
+    } else {
+        params
+            .restitution_override
+            .unwrap_or(
+                body_a.restitution_for_normal(contact.normal)
+                    * body_b.restitution_for_normal(contact.normal),
+            )
+            .clamp(0.0, 1.0)
+    };
+    // the bias is part of the impulse, so every body gets `bias_vel * inverse_mass / k_n` of it
+    // and the lighter body is pushed out the most. It only corrects the penetration,
+    // bouncing it back out would add energy
+    let p_n = f32::max(((1.0 + restitution) * -v_n + bias_vel) / k_n, 0.0);
+
+    // friction impulse
+    // the friction available depends on how hard the bodies were pressed together over all
+    // iterations so far, not just in this one. So the total tangent impulse is clamped
+    // and only the difference to what was applied before is applied now
+    let max_friction = combined_mu(body_a, body_b, tangent) * (params.accumulated_normal + p_n);
+    let old_tangent = params.accumulated_tangent;
+    let new_tangent = f32::clamp(old_tangent - v_t / k_t, -max_friction, max_friction);
+    let p_t = new_tangent - old_tangent;
+
+    (p_n, p_t)
+}
+
+/// The velocity a body leaves an immovable surface with when it hits it with `incoming`.
+/// `normal` points out of the surface toward the body and has to be normalized.
+/// The part of the velocity along the normal is reversed and scaled by the restitution, the part
+/// along the surface is kept as if there was no friction. A body that is already moving away
+/// keeps its velocity. This is what the solver does for a single contact with a static body.
+pub fn bounce_velocity(incoming: Vec2, normal: Vec2, restitution: f32) -> Vec2 {
+    let v_n = incoming.dot(normal);
+    if v_n >= 0.0 {
+        return incoming;
+    }
+    incoming - (1.0 + restitution.clamp(0.0, 1.0)) * v_n * normal
+}
+
+fn resolve_interpenetration(
+    objects: &mut [Object],
+    contact: &mut Contact,
+    mut params: ContactParams,
+    accumulated: &mut AccumulatedImpulse,
+) {
+    let (a, b) = get_two_mut(objects, contact.body_a_index, contact.body_b_index);
+    let body_a = a.body.as_mut().unwrap();
+    let body_b = b.body.as_mut().unwrap();
+
+    params.accumulated_normal = accumulated.normal;
+    params.accumulated_tangent = accumulated.tangent;
+    let (p_n, p_t) = compute_contact_impulse(body_a, body_b, contact, &params);
+    accumulated.normal += p_n;
+    accumulated.tangent += p_t;
+
+    // the later iterations see the bodies already moving apart, so keep the speed of the first one
+    let v_n = contact_relative_velocity(body_a, body_b, contact).dot(contact.normal);
+    contact.approach_speed = contact.approach_speed.max(-v_n);
+    contact.normal_impulse = accumulated.normal;
+    contact.tangent_impulse = accumulated.tangent;
+    contact.mu = combined_mu(body_a, body_b, contact.normal.perp());
+
+    let p_friction = p_t * contact.normal.perp();
+    let p = p_n * contact.normal;
+
+    // both act at the surface, away from the center, so they also make the bodies spin
+    if !body_a.is_static {
+        body_a.apply_impulse_at_point(-(p + p_friction), contact.point);
+    }
+    if !body_b.is_static {
+        body_b.apply_impulse_at_point(p + p_friction, contact.point);
+    }
+}
+
+fn correct_penetration(objects: &mut [Object], contact: &Contact) {
+    let distance = BIAS_FACTOR * f32::max(0.0, contact.pen_depth - SLOP);
+    move_apart(objects, contact, distance);
+}
+
+/// Moves the bodies `distance` apart along the contact normal without changing their velocity.
+/// The lighter body is moved further.
+fn move_apart(objects: &mut [Object], contact: &Contact, distance: f32) {
+    let (a, b) = get_two_mut(objects, contact.body_a_index, contact.body_b_index);
+    let body_a = a.body.as_mut().unwrap();
+    let body_b = b.body.as_mut().unwrap();
+
+    let k_n = body_a.inverse_mass + body_b.inverse_mass;
+    if k_n == 0.0 {
+        return;
+    }
+
+    let correction = distance / k_n;
+    if !body_a.is_static {
+        body_a.position -= contact.normal * correction * body_a.inverse_mass;
+    }
+    if !body_b.is_static {
+        body_b.position += contact.normal * correction * body_b.inverse_mass;
+    }
+}
+
+/// Fills `contacts` with the contacts between all objects. The buffer is cleared first.
+/// Returns the number of pairs that went through the narrow phase.
+fn check_collision(
+    objects: &[Object],
+    name_filter: &NameFilter,
+    contacts: &mut Vec<Contact>,
+) -> usize {
+    contacts.clear();
+    let mut pair_tests = 0;
+    // match the names once per object instead of once per pair
+    let name_masks: Vec<u64> = if name_filter.is_empty() {
+        vec![]
+    } else {
+        objects
+            .iter()
+            .map(|object| name_filter.mask(&object.name))
+            .collect()
+    };
+    for (i, a) in objects.iter().enumerate() {
+        for (b_index, b) in objects.iter().enumerate().skip(i + 1) {
+            if !a.enabled || !b.enabled {
+                continue;
+            }
+            let (Some(collider_a), Some(body_a)) = (&a.collider, &a.body) else {
+                continue;
+            };
+            let (Some(collider_b), Some(body_b)) = (&b.collider, &b.body) else {
+                continue;
+            };
+            // neither body can ever be moved by the contact, so there is nothing to resolve.
+            // Sensors still report their overlaps
+            let is_immovable = |body: &RigidBody2D| body.is_static || body.inverse_mass == 0.0;
+            if !a.is_sensor && !b.is_sensor && is_immovable(body_a) && is_immovable(body_b) {
+                continue;
+            }
+            if !name_masks.is_empty()
+                && !NameFilter::masks_allow(name_masks[i], name_masks[b_index])
+            {
+                continue;
+            }
+
+            pair_tests += 1;
+            if let Some(contact) = collider_a.collides_with(body_a, body_b, collider_b, i, b_index)
+            {
+                contacts.push(contact);
+            }
+        }
+    }
+    pair_tests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Collider, ObjectBuilder, RigidBody2DBuilder};
+    use approx::assert_relative_eq;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn circle(radius: f32) -> Collider {
+        Collider::Circle {
+            offset: Vec2::ZERO,
+            radius,
+        }
+    }
+
+    /// a box of the given size with the body in its center
+    fn centered_box(size: Vec2) -> Collider {
+        Collider::AABB {
+            min: -size / 2.0,
+            max: size / 2.0,
+        }
+    }
+
+    /// an object whose body has the shape of its collider
+    fn object(body: RigidBody2DBuilder, shape: Collider) -> Object {
+        ObjectBuilder::new()
+            .with_body(body.with_shape(shape.clone()).build())
+            .with_collider(shape)
+            .with_color(RED)
+            .build()
+    }
+
+    fn static_floor() -> Object {
+        let floor = Collider::AABB {
+            min: vec2(-10.0, -1.0),
+            max: vec2(10.0, 0.0),
+        };
+        object(RigidBody2DBuilder::new().make_static(), floor)
+    }
+
+    fn static_wall(min_x: f32) -> Object {
+        let wall = Collider::AABB {
+            min: vec2(min_x, -5.0),
+            max: vec2(min_x + 0.1, 5.0),
+        };
+        object(RigidBody2DBuilder::new().make_static(), wall)
+    }
+
+    fn ball(position: Vec2, radius: f32, restitution: f32) -> Object {
+        let body = RigidBody2DBuilder::new()
+            .with_position(position)
+            .with_restitution(restitution);
+        object(body, circle(radius))
+    }
+
+    /// a static circle that only reports overlaps
+    fn sensor(position: Vec2, radius: f32) -> Object {
+        let body = RigidBody2DBuilder::new()
+            .make_static()
+            .with_position(position);
+        let mut sensor = object(body, circle(radius));
+        sensor.is_sensor = true;
+        sensor
+    }
+
+    fn moving_body(vel: Vec2, restitution: f32, mu: f32) -> RigidBody2D {
+        RigidBody2DBuilder::new()
+            .with_vel(vel)
+            .with_restitution(restitution)
+            .with_mu(mu)
+            .build()
+    }
+
+    fn contact_along(normal: Vec2) -> Contact {
+        Contact {
+            normal,
+            body_b_index: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn elastic_head_on_impulse_swaps_velocities() {
+        let a = moving_body(vec2(2.0, 0.0), 1.0, 0.0);
+        let b = moving_body(vec2(-1.0, 0.0), 1.0, 0.0);
+        let params = ContactParams {
+            dt: 1.0 / 60.0,
+            ..Default::default()
+        };
+        let (p_n, p_t) = compute_contact_impulse(&a, &b, &contact_along(Vec2::X), &params);
+
+        // equal masses exchange their velocities, so a loses 3 units of speed
+        assert_relative_eq!(p_n, 3.0);
+        assert_relative_eq!(p_t, 0.0);
+    }
+
+    #[test]
+    fn separating_bodies_get_no_impulse() {
+        let a = moving_body(vec2(-1.0, 0.0), 0.5, 0.5);
+        let b = moving_body(vec2(1.0, 0.0), 0.5, 0.5);
+        let params = ContactParams {
+            dt: 1.0 / 60.0,
+            ..Default::default()
+        };
+        let (p_n, p_t) = compute_contact_impulse(&a, &b, &contact_along(Vec2::X), &params);
+        assert_relative_eq!(p_n, 0.0);
+        assert_relative_eq!(p_t, 0.0);
+    }
+
+    #[test]
+    fn friction_impulse_is_clamped_to_the_friction_cone() {
+        // a lands on b while sliding fast, mu = 0.5 * 0.5
+        let a = moving_body(vec2(10.0, -1.0), 0.0, 0.5);
+        let b = moving_body(Vec2::ZERO, 0.0, 0.5);
+        let params = ContactParams {
+            dt: 1.0 / 60.0,
+            ..Default::default()
+        };
+        let (p_n, p_t) = compute_contact_impulse(&a, &b, &contact_along(Vec2::NEG_Y), &params);
+
+        assert_relative_eq!(p_n, 0.5);
+        assert_relative_eq!(p_t.abs(), 0.25 * p_n);
+    }
+
+    #[test]
+    fn off_center_normal_impulse_includes_the_inertia() {
+        // a lands on b with the contact point 1 to the side of both centers
+        let a = moving_body(vec2(0.0, -1.0), 0.0, 0.0);
+        let b = moving_body(Vec2::ZERO, 0.0, 0.0);
+        let contact = Contact {
+            point: vec2(1.0, 0.0),
+            ..contact_along(Vec2::NEG_Y)
+        };
+        let params = ContactParams {
+            dt: 1.0 / 60.0,
+            ..Default::default()
+        };
+        let (p_n, _) = compute_contact_impulse(&a, &b, &contact, &params);
+
+        // k_n = 1 + 1 for the masses and 1 + 1 for the rotation, as the inverse inertia is 1
+        assert_relative_eq!(p_n, 0.25);
+    }
+
+    // how far a measured bounce height may be off, relative to the expected height
+    const BOUNCE_HEIGHT_TOLERANCE: f32 = 0.05;
+
+    #[test]
+    fn dropped_ball_bounces_to_restitution_squared_and_settles() {
+        let mut world = World::new();
+        world.restitution_threshold = 0.5;
+        let mut floor = static_floor();
+        floor.body.as_mut().unwrap().restitution = 1.0;
+        world.add(floor);
+
+        let radius = 0.5;
+        let drop_height = 5.0;
+        let restitution = 0.5;
+        let handle = world.add(ball(vec2(0.0, drop_height + radius), radius, restitution));
+
+        // a small step keeps the penetration at the impact small, and with it the energy
+        // the penetration bias adds to the bounce
+        let dt = 1.0 / 240.0;
+        let mut apexes = vec![];
+        let mut prev_vel_y = 0.0;
+        for _ in 0..(10.0 / dt) as usize {
+            world.step(dt);
+            let body = world.get(handle).unwrap().body.as_ref().unwrap();
+            let height = body.position.y - radius;
+            // a resting ball sinks in by the slop and jitters there, which is not a bounce
+            if prev_vel_y > 0.0 && body.vel.y <= 0.0 && height > 0.0 {
+                apexes.push(height);
+            }
+            prev_vel_y = body.vel.y;
+        }
+
+        let expected = restitution * restitution * drop_height;
+        assert!(
+            (apexes[0] - expected).abs() <= BOUNCE_HEIGHT_TOLERANCE * expected,
+            "first bounce reached {}, expected {expected}",
+            apexes[0]
+        );
+        assert!(
+            apexes.len() < 10,
+            "still bouncing after {} bounces",
+            apexes.len()
+        );
+        let body = world.get(handle).unwrap().body.as_ref().unwrap();
+        // gravity is integrated after the contacts are solved, so even a resting body
+        // ends the step with the velocity gravity gave it during that step
+        let resting_speed = world.gravity.length() * dt;
+        assert!(
+            body.vel.length() <= resting_speed + 0.01,
+            "ball didn't settle: {}",
+            body.vel
+        );
+    }
+
+    #[test]
+    fn rigid_pendulum_keeps_its_energy() {
+        let mut world = World::new();
+        world.joint_iterations = 20;
+        let point_mass =
+            |body: RigidBody2D| ObjectBuilder::new().with_body(body).with_color(RED).build();
+        let pivot = world.add(point_mass(RigidBody2DBuilder::new().make_static().build()));
+        // released from the horizontal, so it swings through the whole half circle
+        let length = 2.0;
+        let bob = world.add(point_mass(
+            RigidBody2DBuilder::new()
+                .with_position(vec2(length, 0.0))
+                .build(),
+        ));
+        assert!(world.add_distance_joint(pivot, bob, 1.0));
+
+        let gravity = world.gravity.length();
+        let energy = |world: &World| {
+            let body = world.get(bob).unwrap().body.as_ref().unwrap();
+            body.kinetic_energy() + gravity * body.position.y / body.inverse_mass
+        };
+        let baseline = energy(&world);
+        // the energy the bob gains falling from the pivot height to the bottom of the swing
+        let swing_energy = gravity * length;
+
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+            let drift = (energy(&world) - baseline).abs();
+            assert!(
+                drift < 0.1 * swing_energy,
+                "energy drifted by {drift}, more than 10% of {swing_energy}"
+            );
+        }
+    }
+
+    #[test]
+    fn restitution_above_one_does_not_add_energy() {
+        let mut world = World::new();
+        let mut floor = static_floor();
+        floor.body.as_mut().unwrap().restitution = 2.0; // bypasses the clamping of the builder
+        world.add(floor);
+        let start_height = 5.0;
+        let handle = world.add(ball(vec2(0.0, start_height), 0.5, 2.0));
+
+        let mut max_height = f32::MIN;
+        let mut has_bounced = false;
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+            let body = world.get(handle).unwrap().body.as_ref().unwrap();
+            if body.vel.y > 0.0 {
+                has_bounced = true;
+            }
+            if has_bounced {
+                max_height = max_height.max(body.position.y);
+            }
+        }
+
+        assert!(has_bounced);
+        assert!(
+            max_height <= start_height + 0.05,
+            "bounced up to {max_height}, higher than the start at {start_height}"
+        );
+    }
+
+    #[test]
+    fn colliding_circles_conserve_momentum() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        for (position, vel, inverse_mass, radius) in [
+            (vec2(-2.0, 0.0), vec2(3.0, 0.5), 1.0, 0.5),
+            (vec2(2.0, 0.2), vec2(-1.0, 0.0), 0.25, 1.0),
+        ] {
+            let body = RigidBody2DBuilder::new()
+                .with_position(position)
+                .with_vel(vel)
+                .with_inverse_mass(inverse_mass)
+                .with_restitution(0.5);
+            world.add(object(body, circle(radius)));
+        }
+
+        let momentum_before = world.total_momentum();
+        let mut has_collided = false;
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            has_collided |= !world.contacts.is_empty();
+        }
+
+        assert!(has_collided);
+        let momentum_after = world.total_momentum();
+        assert_relative_eq!(momentum_after.x, momentum_before.x, epsilon = 1e-3);
+        assert_relative_eq!(momentum_after.y, momentum_before.y, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn box_stack_on_a_high_friction_floor_stays_aligned() {
+        let mut world = World::new();
+        let mut floor = static_floor();
+        floor.body.as_mut().unwrap().mu = 1.0;
+        world.add(floor);
+        let boxes: Vec<ObjectHandle> = (0..3)
+            .map(|i| {
+                let body = RigidBody2DBuilder::new()
+                    .with_position(vec2(0.0, 0.5 + i as f32))
+                    .with_restitution(0.0)
+                    .with_mu(1.0);
+                world.add(object(body, centered_box(Vec2::ONE)))
+            })
+            .collect();
+        // nudge the bottom box, the weight of the whole stack should hold it in place
+        world.get_mut(boxes[0]).unwrap().body.as_mut().unwrap().vel = vec2(1.0, 0.0);
+
+        for _ in 0..300 {
+            world.step(1.0 / 60.0);
+        }
+
+        let body = |handle| world.get(handle).unwrap().body.as_ref().unwrap();
+        let bottom_x = body(boxes[0]).position.x;
+        assert!(
+            bottom_x.abs() < 0.05,
+            "the bottom box slid to x = {bottom_x}"
+        );
+        for (i, &handle) in boxes.iter().enumerate() {
+            let body = body(handle);
+            assert!(
+                (body.position.x - bottom_x).abs() < 0.05,
+                "box {i} is at x = {}, the bottom box at {bottom_x}",
+                body.position.x
+            );
+            assert!(
+                (body.position.y - (0.5 + i as f32)).abs() < 0.05,
+                "box {i} is at height {}",
+                body.position.y
+            );
+            assert!(
+                body.vel.length() < 0.2,
+                "box {i} still moves at {}",
+                body.vel
+            );
+        }
+    }
+
+    #[test]
+    fn satellites_keep_a_circular_orbit() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        let planet = world.add_static(circle(1.0), Vec2::ZERO, PhysicsMaterial::default());
+        let gravity = PointGravity::new(planet, 100.0);
+        world.add_point_gravity(gravity);
+
+        // a light and a heavy satellite on the same orbit, they should stay next to each other
+        let radius = 10.0;
+        let satellites: Vec<ObjectHandle> = [1.0, 0.01]
+            .into_iter()
+            .map(|inverse_mass| {
+                let body = RigidBody2DBuilder::new()
+                    .with_position(vec2(radius, 0.0))
+                    .with_vel(vec2(0.0, gravity.orbital_speed(radius)))
+                    .with_inverse_mass(inverse_mass);
+                let mut satellite = object(body, circle(0.2));
+                satellite.is_sensor = true;
+                world.add(satellite)
+            })
+            .collect();
+
+        // about five full orbits
+        let period = std::f32::consts::TAU * radius / gravity.orbital_speed(radius);
+        let dt = 1.0 / 60.0;
+        for _ in 0..(5.0 * period / dt) as usize {
+            world.step(dt);
+            for &handle in &satellites {
+                let distance = world
+                    .get(handle)
+                    .unwrap()
+                    .body
+                    .as_ref()
+                    .unwrap()
+                    .position
+                    .length();
+                assert!(
+                    (distance - radius).abs() < 0.05 * radius,
+                    "the satellite drifted to a distance of {distance}"
+                );
+            }
+        }
+        let position = |handle| world.get(handle).unwrap().body.as_ref().unwrap().position;
+        assert!(position(satellites[0]).distance(position(satellites[1])) < 1e-3);
+    }
+
+    #[test]
+    fn sliding_ball_starts_rolling_because_of_friction() {
+        let mut world = World::new();
+        world.add(static_floor());
+        let radius = 0.5;
+        let start_speed = 3.0;
+        let mut ball = ball(vec2(-8.0, radius), radius, 0.0);
+        ball.body.as_mut().unwrap().vel = vec2(start_speed, 0.0);
+        let handle = world.add(ball);
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+
+        let body = world.get(handle).unwrap().body.as_ref().unwrap();
+        // rolling towards +x turns clockwise, and the contact point stands still
+        assert!(body.angular_vel < 0.0);
+        let lever = body.position.y; // from the center down to the floor surface
+        assert_relative_eq!(body.vel.x, -body.angular_vel * lever, epsilon = 0.01);
+        // a disc keeps 2/3 of its speed once it rolls, the rest goes into the rotation
+        assert_relative_eq!(body.vel.x, start_speed * 2.0 / 3.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn anisotropic_friction_slows_sliding_along_each_axis_differently() {
+        // slides a box over a surface with normal `up`, with gravity pushing it onto the surface
+        let speed_after_sliding = |up: Vec2, slide_dir: Vec2| {
+            let mut world = World::new();
+            world.gravity = -up * 9.81;
+            // 20 long and 1 thick, with its top side through the origin
+            let (corner_a, corner_b) = (-up - up.perp() * 10.0, up.perp() * 10.0);
+            let surface = Collider::AABB {
+                min: corner_a.min(corner_b),
+                max: corner_a.max(corner_b),
+            };
+            world.add(object(
+                RigidBody2DBuilder::new().make_static().with_mu(1.0),
+                surface,
+            ));
+            let body = RigidBody2DBuilder::new()
+                .with_position(up * 0.5)
+                .with_vel(slide_dir * 3.0)
+                .with_restitution(0.0)
+                .with_anisotropic_friction(Vec2::X, 0.1, 1.0);
+            let handle = world.add(object(body, centered_box(Vec2::ONE)));
+            for _ in 0..10 {
+                world.step(1.0 / 60.0);
+            }
+            world
+                .get(handle)
+                .unwrap()
+                .body
+                .as_ref()
+                .unwrap()
+                .vel
+                .dot(slide_dir)
+        };
+
+        let along_x = speed_after_sliding(Vec2::Y, Vec2::X);
+        let along_y = speed_after_sliding(Vec2::X, Vec2::Y);
+        assert!(along_x < 3.0, "sliding along x kept all its speed");
+        assert!(
+            3.0 - along_y > 5.0 * (3.0 - along_x),
+            "sliding along y slowed from 3 to {along_y}, along x to {along_x}"
+        );
+    }
+
+    #[test]
+    fn ball_resting_on_a_bouncy_floor_comes_to_rest() {
+        let mut world = World::new();
+        let mut floor = static_floor();
+        floor.body.as_mut().unwrap().restitution = 0.8;
+        world.add(floor);
+        let radius = 0.5;
+        let handle = world.add(ball(vec2(0.0, radius), radius, 1.0));
+
+        // give the ball a second to settle into the floor, from then on it must not move
+        let dt = 1.0 / 60.0;
+        for _ in 0..60 {
+            world.step(dt);
+        }
+        let rest_height = world.get(handle).unwrap().body.as_ref().unwrap().position.y;
+        for _ in 0..240 {
+            world.step(dt);
+            let body = world.get(handle).unwrap().body.as_ref().unwrap();
+            assert!(
+                body.vel.length() < 0.01,
+                "the ball still moves at {}",
+                body.vel
+            );
+            assert_relative_eq!(body.position.y, rest_height, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn bodies_on_the_same_floor_form_separate_islands_until_joined() {
+        let mut world = World::new();
+        world.add(static_floor());
+        let left = world.add(ball(vec2(-3.0, 0.45), 0.5, 0.0));
+        let right = world.add(ball(vec2(3.0, 0.45), 0.5, 0.0));
+
+        world.step(1.0 / 60.0);
+        // the floor is static, so it doesn't connect the balls
+        assert_eq!(world.step_stats().contact_count, 2);
+        assert_eq!(world.step_stats().island_count, 2);
+
+        world.add_distance_joint(left, right, 1.0);
+        world.step(1.0 / 60.0);
+        assert_eq!(world.step_stats().island_count, 1);
+    }
+
+    #[test]
+    fn overlapping_static_bodies_are_not_tested() {
+        let mut world = World::new();
+        world.add(static_floor());
+        // a wall sunk into the floor
+        world.add_static(
+            Collider::AABB {
+                min: vec2(-0.5, -2.0),
+                max: vec2(0.5, 2.0),
+            },
+            Vec2::ZERO,
+            PhysicsMaterial::default(),
+        );
+
+        world.step(1.0 / 60.0);
+        assert!(world.contacts.is_empty());
+        assert!(world.overlapping_pairs().is_empty());
+        assert_eq!(world.step_stats().pair_tests, 0);
+    }
+
+    #[test]
+    fn gravity_before_solve_keeps_resting_bodies_from_sinking() {
+        let resting_height = |sequencing| {
+            let mut world = World::new();
+            world.gravity_sequencing = sequencing;
+            world.add(static_floor());
+            let handle = world.add(ball(vec2(0.0, 0.5), 0.5, 0.0));
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+            }
+            world.get(handle).unwrap().body.as_ref().unwrap().position.y
+        };
+
+        // the contacts cancel the gravity before it moves the ball into the floor
+        let before = resting_height(GravitySequencing::BeforeSolve);
+        let after = resting_height(GravitySequencing::AfterSolve);
+        assert_relative_eq!(before, 0.5, epsilon = 5e-3);
+        assert!(0.5 - after > 2.0 * (0.5 - before));
+    }
+
+    #[test]
+    fn contacts_for_points_the_normal_away_from_the_object() {
+        let mut world = World::new();
+        let floor = world.add(static_floor());
+        let ball = world.add(ball(vec2(0.0, 0.45), 0.5, 0.0));
+        world.step(1.0 / 60.0);
+
+        let touching = world.contacts_for(ball);
+        assert_eq!(touching.len(), 1);
+        assert_eq!(touching[0].0, floor);
+        assert!(touching[0].1.dot(world.up()) < -0.99);
+
+        let touching = world.contacts_for(floor);
+        assert_eq!(touching[0].0, ball);
+        assert!(touching[0].1.dot(world.up()) > 0.99);
+    }
+
+    #[test]
+    fn light_ball_doesnt_lift_a_heavy_box() {
+        let box_height = |with_ball: bool, position_iterations: usize| {
+            let mut world = World::new();
+            world.position_iterations = position_iterations;
+            world.add(static_floor());
+            let body = RigidBody2DBuilder::new()
+                .with_position(vec2(0.0, 0.5))
+                .with_inverse_mass(0.01)
+                .with_restitution(0.0);
+            let heavy_box = world.add(object(body, centered_box(Vec2::ONE)));
+            if with_ball {
+                let mut light_ball = ball(vec2(0.0, 1.2), 0.25, 0.0);
+                light_ball.body.as_mut().unwrap().inverse_mass = 100.0;
+                world.add(light_ball);
+            }
+            let mut highest = f32::MIN;
+            for _ in 0..120 {
+                world.step(1.0 / 60.0);
+                let y = world
+                    .get(heavy_box)
+                    .unwrap()
+                    .body
+                    .as_ref()
+                    .unwrap()
+                    .position
+                    .y;
+                highest = highest.max(y);
+            }
+            highest
+        };
+
+        // the ball starts sunk into the box, pushing them apart has to move the ball.
+        // Without position iterations the velocity bias does that, with them `correct_penetration`
+        for position_iterations in [0, 4] {
+            assert_relative_eq!(
+                box_height(true, position_iterations),
+                box_height(false, position_iterations),
+                epsilon = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn ball_deep_inside_the_floor_is_separated_at_once() {
+        let mut world = World::new();
+        world.max_penetration = Some(0.5);
+        let floor = world.add(static_floor());
+        let ball = world.add(ball(vec2(0.0, -0.3), 0.5, 0.0));
+
+        world.step(1.0 / 60.0);
+        let separations = world.emergency_separations();
+        assert_eq!(separations.len(), 1);
+        assert_eq!((separations[0].a, separations[0].b), (floor, ball));
+        assert_relative_eq!(separations[0].pen_depth, 0.8, epsilon = 1e-4);
+        let y = world.get(ball).unwrap().body.as_ref().unwrap().position.y;
+        assert!(y > 0.45, "the ball is still at y = {y}");
+
+        world.step(1.0 / 60.0);
+        assert!(world.emergency_separations().is_empty());
+    }
+
+    #[test]
+    fn bounce_velocity_reverses_the_normal_part() {
+        let normal = vec2(0.0, 1.0);
+        let bounced = bounce_velocity(vec2(3.0, -4.0), normal, 0.5);
+        assert_relative_eq!(bounced.x, 3.0);
+        assert_relative_eq!(bounced.y, 2.0);
+
+        let elastic = bounce_velocity(vec2(0.0, -4.0), normal, 1.0);
+        assert_relative_eq!(elastic.y, 4.0);
+
+        // moving away from the surface already
+        let leaving = bounce_velocity(vec2(1.0, 2.0), normal, 0.5);
+        assert_eq!(leaving, vec2(1.0, 2.0));
+    }
+
+    #[test]
+    fn bounce_velocity_matches_the_solver() {
+        for restitution in [0.0, 0.5, 1.0] {
+            let mut world = World::new();
+            world.gravity = GRAVITY_ZERO;
+            // correct the penetration with position passes, a bias would add to the bounce
+            world.position_iterations = 4;
+            let mut floor = static_floor();
+            floor.body.as_mut().unwrap().restitution = 1.0;
+            world.add(floor);
+            let incoming = vec2(2.0, -5.0);
+            let mut ball = ball(vec2(0.0, 0.55), 0.5, restitution);
+            let body = ball.body.as_mut().unwrap();
+            body.vel = incoming;
+            body.mu = 0.0;
+            let ball = world.add(ball);
+
+            for _ in 0..10 {
+                world.step(1.0 / 60.0);
+                if !world.collision_events().is_empty() {
+                    break;
+                }
+            }
+            assert_eq!(world.collision_events().len(), 1);
+
+            let expected = bounce_velocity(incoming, world.up(), restitution);
+            let vel = world.get(ball).unwrap().body.as_ref().unwrap().vel;
+            assert_relative_eq!(vel.x, expected.x, epsilon = 1e-4);
+            assert_relative_eq!(vel.y, expected.y, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn step_n_is_the_same_as_stepping_in_a_loop() {
+        let setup = || {
+            let mut world = World::new();
+            world.add(static_floor());
+            let handle = world.add(ball(vec2(0.0, 2.0), 0.5, 0.5));
+            (world, handle)
+        };
+        let dt = 1.0 / 60.0;
+
+        let (mut looped, handle) = setup();
+        let mut contact_count = 0;
+        for _ in 0..120 {
+            looped.step(dt);
+            contact_count += looped.step_stats().contact_count;
+        }
+
+        let (mut batched, _) = setup();
+        let stats = batched.step_n(dt, 120);
+        assert_eq!(stats.contact_count, contact_count);
+        assert_eq!(batched.step_stats(), looped.step_stats());
+        let position = |world: &World| world.get(handle).unwrap().body.as_ref().unwrap().position;
+        assert_eq!(position(&batched), position(&looped));
+    }
+
+    #[test]
+    fn objects_filtered_by_name_pass_through_each_other() {
+        let mut world = World::new();
+        world.name_filter.ignore_pair("bullet*", "floor");
+        let mut floor = static_floor();
+        floor.name = "floor".to_string();
+        world.add(floor);
+        let mut bullet = ball(vec2(0.0, 0.45), 0.5, 0.0);
+        bullet.name = "bullet_1".to_string();
+        let bullet = world.add(bullet);
+        let resting = world.add(ball(vec2(3.0, 0.45), 0.5, 0.0));
+
+        world.step_n(1.0 / 60.0, 30);
+        let height = |handle| world.get(handle).unwrap().body.as_ref().unwrap().position.y;
+        assert!(height(bullet) < 0.0);
+        assert!(height(resting) > 0.45);
+    }
+
+    #[test]
+    fn too_few_iterations_are_reported_as_not_converged() {
+        let run = |velocity_iterations| {
+            let mut world = World::new();
+            world.gravity = GRAVITY_ZERO;
+            world.velocity_iterations = velocity_iterations;
+            // a row of touching balls, the first one is pushed into the others
+            for i in 0..4 {
+                let mut ball = ball(vec2(i as f32 * 0.99, 0.0), 0.5, 0.0);
+                if i == 0 {
+                    ball.body.as_mut().unwrap().vel = vec2(5.0, 0.0);
+                }
+                world.add(ball);
+            }
+            world.step(1.0 / 60.0);
+            world.step_stats()
+        };
+
+        let stats = run(1);
+        assert!(!stats.converged);
+        assert!(stats.residual > 0.01);
+
+        let stats = run(50);
+        assert!(stats.converged, "residual {}", stats.residual);
+    }
+
+    #[test]
+    fn duplicate_adds_a_resting_copy_at_the_offset() {
+        let mut world = World::new();
+        let mut original = ball(vec2(1.0, 2.0), 0.5, 0.3);
+        original.body.as_mut().unwrap().vel = vec2(3.0, 0.0);
+        original.name = "crate".to_string();
+        let original = world.add(original);
+
+        let copy = world.duplicate(original, vec2(2.0, 0.0)).unwrap();
+        assert_ne!(copy, original);
+        assert_eq!(world.objects().len(), 2);
+
+        let object = world.get(copy).unwrap();
+        assert_eq!(object.name, "crate");
+        let body = object.body.as_ref().unwrap();
+        assert_eq!(body.position, vec2(3.0, 2.0));
+        assert_eq!(body.vel, Vec2::ZERO);
+        assert_relative_eq!(body.restitution, 0.3);
+
+        let body = world.get(original).unwrap().body.as_ref().unwrap();
+        assert_eq!(body.position, vec2(1.0, 2.0));
+        assert_eq!(body.vel, vec2(3.0, 0.0));
+
+        world.remove(original);
+        assert!(world.duplicate(original, Vec2::ZERO).is_none());
+    }
+
+    #[test]
+    fn import_drops_joints_and_gravities_of_missing_objects() {
+        let mut other = World::new();
+        let a = other.add(ball(vec2(0.0, 1.0), 0.5, 0.0));
+        let b = other.add(ball(vec2(2.0, 1.0), 0.5, 0.0));
+        let removed = other.add(ball(vec2(4.0, 1.0), 0.5, 0.0));
+        other.remove(removed);
+        other.add_joint(DistanceJoint::new(a, b, 2.0, 1.0));
+        other.add_joint(DistanceJoint::new(a, removed, 4.0, 1.0));
+        other.add_point_gravity(PointGravity::new(b, 10.0));
+        other.add_point_gravity(PointGravity::new(removed, 10.0));
+
+        let mut world = World::new();
+        world.add(static_floor());
+        let handle_map = world.import(other, vec2(1.0, 0.0));
+
+        assert_eq!(handle_map.len(), 2);
+        assert_eq!(world.joints().len(), 1);
+        assert_eq!(world.joints()[0].a, handle_map[&a]);
+        assert_eq!(world.joints()[0].b, handle_map[&b]);
+        assert_eq!(world.point_gravities().len(), 1);
+        assert_eq!(world.point_gravities()[0].attractor, handle_map[&b]);
+    }
+
+    #[test]
+    fn ccd_stops_a_fast_ball_at_a_thin_wall() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        world.ccd_speed_threshold = Some(10.0);
+        world.add(static_wall(5.0));
+        let mut bullet = ball(Vec2::ZERO, 0.2, 0.0);
+        bullet.body.as_mut().unwrap().vel = vec2(200.0, 0.0);
+        let bullet = world.add(bullet);
+
+        world.step_n(1.0 / 60.0, 10);
+        let body = world.get(bullet).unwrap().body.as_ref().unwrap();
+        assert!(
+            body.position.x < 5.0,
+            "the ball tunneled to {}",
+            body.position.x
+        );
+        assert!(body.vel.x <= 0.0);
+    }
+
+    #[test]
+    fn ccd_lets_a_fast_ball_that_almost_touches_reach_the_wall() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        world.ccd_speed_threshold = Some(10.0);
+        world.add(static_wall(5.0));
+        // closer to the wall than the tolerance of the sweep, but not touching it
+        let mut bullet = ball(vec2(5.0 - 0.2 - 0.0005, 0.0), 0.2, 0.0);
+        bullet.body.as_mut().unwrap().vel = vec2(200.0, 0.0);
+        let bullet = world.add(bullet);
+
+        world.step_n(1.0 / 60.0, 3);
+        let body = world.get(bullet).unwrap().body.as_ref().unwrap();
+        assert!(
+            body.position.x < 5.0,
+            "the ball tunneled to {}",
+            body.position.x
+        );
+        // the ball reached the wall and the contact stopped it
+        assert!(
+            body.vel.x <= 0.0,
+            "the ball still moves with {}",
+            body.vel.x
+        );
+    }
+
+    #[test]
+    fn sensor_contacts_dont_use_up_the_contact_budget() {
+        let mut world = World::new();
+        world.contact_budget = Some(1);
+        world.add(static_floor());
+        let ball = world.add(ball(vec2(0.0, 0.45), 0.5, 0.0));
+        // a sensor that overlaps the ball much deeper than the floor does
+        world.add(sensor(vec2(0.0, 0.5), 1.0));
+
+        world.step(1.0 / 60.0);
+        assert_eq!(world.contacts.len(), 1);
+        assert_eq!(world.contacts_for(ball).len(), 1);
+        assert!(world.contacts_for(ball)[0].1.dot(world.up()) < -0.99);
+    }
+
+    #[test]
+    fn play_restores_the_recorded_states_by_handle() {
+        let mut world = World::new();
+        world.add(static_floor());
+        let first = world.add(ball(vec2(-2.0, 3.0), 0.5, 0.0));
+        let second = world.add(ball(vec2(2.0, 5.0), 0.5, 0.0));
+
+        world.start_recording();
+        world.step_n(1.0 / 60.0, 10);
+        world.stop_recording();
+        assert_eq!(world.recorded_frame_count(), 10);
+        let recorded = world.get(second).unwrap().body.as_ref().unwrap().state();
+
+        // the second ball moves to the index of the first one
+        world.remove(first);
+        let added = world.add(ball(vec2(0.0, 8.0), 0.5, 0.0));
+        world.step_n(1.0 / 60.0, 10);
+
+        assert!(world.play(9));
+        assert_eq!(
+            world.get(second).unwrap().body.as_ref().unwrap().state(),
+            recorded
+        );
+        let body = world.get(added).unwrap().body.as_ref().unwrap();
+        assert_ne!(body.state(), recorded);
+        assert!(body.position.y < 8.0);
+        assert!(!world.play(10));
+    }
+
+    #[test]
+    fn contacts_over_the_budget_keep_their_penetration() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        world.contact_budget = Some(1);
+        world.position_iterations = 4;
+        world.add(static_floor());
+        let deep = world.add(ball(vec2(-3.0, 0.3), 0.5, 0.0));
+        let deferred = world.add(ball(vec2(3.0, 0.4), 0.5, 0.0));
+
+        world.step(1.0 / 60.0);
+        assert_eq!(world.step_stats().contact_count, 1);
+        let height = |handle| world.get(handle).unwrap().body.as_ref().unwrap().position.y;
+        assert!(height(deep) > 0.3, "the deepest contact was not resolved");
+        assert_eq!(height(deferred), 0.4);
+    }
+
+    #[test]
+    fn emergency_separation_doesnt_ask_the_pre_solve_callback_again() {
+        let mut world = World::new();
+        world.max_penetration = Some(0.5);
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        world.set_pre_solve(move |_, _, _| {
+            counter.set(counter.get() + 1);
+            true
+        });
+        world.add(static_floor());
+        world.add(ball(vec2(0.0, -0.3), 0.5, 0.0));
+
+        world.step(1.0 / 60.0);
+        assert_eq!(world.emergency_separations().len(), 1);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn ghost_passes_through_the_objects_the_pre_solve_callback_rejects() {
+        let mut world = World::new();
+        world.detect_every_iteration = true;
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        world.set_pre_solve(move |_, a, b| {
+            counter.set(counter.get() + 1);
+            a.name != "ghost" && b.name != "ghost"
+        });
+        world.add(static_floor());
+        let mut ghost = ball(vec2(-2.0, 0.45), 0.5, 0.0);
+        ghost.name = "ghost".to_string();
+        let ghost = world.add(ghost);
+        let solid = world.add(ball(vec2(2.0, 0.45), 0.5, 0.0));
+
+        world.step(1.0 / 60.0);
+        // once per pair, not once per iteration
+        assert_eq!(calls.get(), 2);
+
+        world.step_n(1.0 / 60.0, 29);
+        let height = |handle| world.get(handle).unwrap().body.as_ref().unwrap().position.y;
+        assert!(
+            height(ghost) < 0.0,
+            "the ghost is still at {}",
+            height(ghost)
+        );
+        assert!(height(solid) > 0.45);
+    }
+
+    #[test]
+    fn deterministic_worlds_dont_depend_on_the_insertion_order() {
+        // a pile of balls and boxes that fall onto each other and the floor
+        let scene: Vec<Object> = (0..8)
+            .map(|i| {
+                let position = vec2((i % 3) as f32 * 0.7 - 0.7, 0.6 + i as f32 * 0.9);
+                if i % 2 == 0 {
+                    ball(position, 0.4, 0.3)
+                } else {
+                    object(
+                        RigidBody2DBuilder::new().with_position(position),
+                        centered_box(vec2(0.8, 0.6)),
+                    )
+                }
+            })
+            .chain([static_floor()])
+            .collect();
+        let run = |reversed: bool| {
+            let mut world = World::new();
+            world.deterministic = true;
+            let mut handles: Vec<ObjectHandle> = if reversed {
+                let mut handles: Vec<_> = scene
+                    .iter()
+                    .rev()
+                    .map(|object| world.add(object.clone()))
+                    .collect();
+                handles.reverse();
+                handles
+            } else {
+                scene
+                    .iter()
+                    .map(|object| world.add(object.clone()))
+                    .collect()
+            };
+            world.step_n(1.0 / 60.0, 120);
+            handles
+                .drain(..)
+                .map(|handle| world.get(handle).unwrap().body.as_ref().unwrap().state())
+                .collect::<Vec<_>>()
+        };
+
+        let forward = run(false);
+        let reversed = run(true);
+        for (i, (a, b)) in forward.iter().zip(&reversed).enumerate() {
+            assert_eq!(a, b, "object {i} ended up in a different state");
+        }
+    }
+
+    #[test]
+    fn fracture_keeps_the_momentum_and_stays_within_the_bounds() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        let mut rock = ball(vec2(2.0, 3.0), 1.0, 0.0);
+        let body = rock.body.as_mut().unwrap();
+        body.vel = vec2(3.0, -1.0);
+        body.angular_vel = 2.0;
+        let rock = world.add(rock);
+        let momentum_before = world.total_momentum();
+
+        let fragments = world.fracture(rock, 9);
+        assert_eq!(fragments.len(), 9);
+        assert!(world.get(rock).is_none());
+        let momentum_after = world.total_momentum();
+        assert_relative_eq!(momentum_after.x, momentum_before.x, epsilon = 1e-4);
+        assert_relative_eq!(momentum_after.y, momentum_before.y, epsilon = 1e-4);
+        for handle in fragments {
+            let object = world.get(handle).unwrap();
+            let (min, max) = object
+                .collider
+                .as_ref()
+                .unwrap()
+                .world_bounds(object.body.as_ref().unwrap().position);
+            assert!(min.cmpge(vec2(1.0, 2.0)).all() && max.cmple(vec2(3.0, 4.0)).all());
+        }
+    }
+
+    #[test]
+    fn ball_passing_a_sensor_enters_and_exits_once() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        let goal = world.add(sensor(Vec2::ZERO, 1.0));
+        let mut player = ball(vec2(-3.0, 0.0), 0.5, 0.0);
+        player.body.as_mut().unwrap().vel = vec2(3.0, 0.0);
+        let player = world.add(player);
+
+        let mut events = vec![];
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            events.extend_from_slice(world.sensor_events());
+        }
+        assert_eq!(
+            events,
+            [
+                SensorEvent::Enter(goal, player),
+                SensorEvent::Exit(goal, player)
+            ]
+        );
+        // the sensor didn't slow the ball down
+        let vel = world.get(player).unwrap().body.as_ref().unwrap().vel;
+        assert_eq!(vel, vec2(3.0, 0.0));
+    }
+
+    #[test]
+    fn an_impact_is_reported_once_with_its_approach_speed() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        world.detect_every_iteration = true;
+        // correct the penetration with position passes, a bias would add to the impulse
+        world.position_iterations = 4;
+        let floor = world.add(static_floor());
+        let mut falling = ball(vec2(0.0, 0.55), 0.5, 0.0);
+        falling.body.as_mut().unwrap().vel = vec2(0.0, -4.0);
+        let falling = world.add(falling);
+
+        world.step_n(1.0 / 60.0, 2);
+        let events = world.collision_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].a, events[0].b), (floor, falling));
+        assert_relative_eq!(events[0].approach_speed, 4.0, epsilon = 1e-4);
+        // the ball weighs 1 and is stopped completely
+        assert_relative_eq!(events[0].normal_impulse, 4.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn validate_finds_and_resets_a_nan_body() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        world.add(ball(vec2(-2.0, 0.0), 0.5, 0.0));
+        let broken = world.add(ball(vec2(1.0, 2.0), 0.5, 0.0));
+        assert_eq!(world.validate(), Ok(()));
+
+        world.get_mut(broken).unwrap().body.as_mut().unwrap().vel = vec2(f32::NAN, 0.0);
+        assert_eq!(world.validate(), Err(vec![broken]));
+
+        world.validate_every_step = true;
+        world.reset_invalid_bodies = true;
+        world.step(1.0 / 60.0);
+        assert_eq!(world.invalid_bodies(), [broken]);
+        let body = world.get(broken).unwrap().body.as_ref().unwrap();
+        assert_eq!(body.position, vec2(1.0, 2.0));
+        assert_eq!(body.vel, Vec2::ZERO);
+        assert_eq!(world.validate(), Ok(()));
+    }
+
+    #[test]
+    fn body_leaving_the_wrap_region_comes_back_on_the_other_side() {
+        let mut world = World::new();
+        world.gravity = GRAVITY_ZERO;
+        world.set_wrap(vec2(-10.0, -5.0), vec2(10.0, 5.0));
+        let mut asteroid = ball(vec2(9.9, 1.0), 0.5, 0.0);
+        asteroid.body.as_mut().unwrap().vel = vec2(12.0, 0.0);
+        let asteroid = world.add(asteroid);
+
+        world.step(1.0 / 60.0);
+        let body = world.get(asteroid).unwrap().body.as_ref().unwrap();
+        assert_relative_eq!(body.position.x, -9.9, epsilon = 1e-4);
+        assert_relative_eq!(body.position.y, 1.0);
+        assert_eq!(body.vel, vec2(12.0, 0.0));
+    }
+
+    #[test]
+    fn point_gravity_pulls_on_the_center_of_mass() {
+        // the collider is 3 to the right of the position, straight below the attractor
+        let shape = Collider::Circle {
+            offset: vec2(3.0, 0.0),
+            radius: 0.5,
+        };
+        let body = RigidBody2DBuilder::new().with_shape(shape).build();
+        let gravity = PointGravity::new(ObjectHandle(0), 10.0);
+
+        let force = gravity.force_on(vec2(3.0, 5.0), &body);
+        assert_relative_eq!(force.x, 0.0);
+        assert_relative_eq!(force.y, 10.0 / 25.0);
+    }
+
+    #[test]
+    fn orbital_system_keeps_its_satellites_on_their_orbits() {
+        let mut world = World::new();
+        let center = vec2(5.0, -2.0);
+        world.spawn_orbital_system(center, 3);
+        let distances = |world: &World| -> Vec<f32> {
+            world
+                .objects()
+                .iter()
+                .filter_map(|object| object.body.as_ref())
+                .filter(|body| !body.is_static)
+                .map(|body| body.position.distance(center))
+                .collect()
+        };
+        let start = distances(&world);
+        assert_eq!(start.len(), 3);
+
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+        }
+        for (start, end) in start.iter().zip(distances(&world)) {
+            assert!(
+                (end - start).abs() < 0.05 * start,
+                "a satellite drifted from {start} to {end}"
+            );
+        }
+        assert!(world.collision_events().is_empty());
+    }
+}